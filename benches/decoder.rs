@@ -0,0 +1,88 @@
+//! Compares capturing frames with a single, persistent decoder against opening a new decoder for
+//! every capture (which is what used to happen). Both seek and decode the same way, so the
+//! difference is only the cost of the decoders. Set `MK_SCREENS_BENCH_VIDEO` to the path of the
+//! video to benchmark with.
+extern crate ffmpeg_next as ffmpeg;
+
+use ffmpeg::{
+    codec::context::Context as CodecContext, decoder::Video as VideoDecoder,
+    format::context::Input, util::frame::video::Video,
+};
+use mk_screens::{settings::Settings, video::VidInfo, Result};
+use std::{
+    env,
+    time::{Duration, Instant},
+};
+
+/// Seeks to the keyframe before `av_time` and decodes the first frame from there.
+fn decode_at(
+    input: &mut Input,
+    decoder: &mut VideoDecoder,
+    stream_idx: usize,
+    av_time: i64,
+) -> Result<()> {
+    input.seek(av_time, ..av_time)?;
+    let mut frame = Video::empty();
+    for (s, packet) in input.packets() {
+        if s.index() != stream_idx || decoder.send_packet(&packet).is_err() {
+            continue;
+        }
+        if decoder.receive_frame(&mut frame).is_ok() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn open_decoder(input: &Input, stream_idx: usize) -> Result<VideoDecoder> {
+    let stream = input.stream(stream_idx).unwrap();
+    Ok(CodecContext::from_parameters(stream.parameters())?
+        .decoder()
+        .video()?)
+}
+
+fn main() -> Result<()> {
+    let Ok(path) = env::var("MK_SCREENS_BENCH_VIDEO") else {
+        eprintln!("MK_SCREENS_BENCH_VIDEO is not set; skipping benchmark.");
+        return Ok(());
+    };
+    ffmpeg::init()?;
+    let settings = Settings::from_defaults()?;
+    let mut info = VidInfo::new(&settings, &path)?;
+    let times = info.generate_capture_times(&settings)?;
+    let stream_idx = info.video_stream_index();
+    let mut input = ffmpeg::format::input(&path)?;
+
+    let start = Instant::now();
+    let mut decoder = open_decoder(&input, stream_idx)?;
+    for timestamp in &times {
+        decoder.flush();
+        decode_at(&mut input, &mut decoder, stream_idx, timestamp.av_time())?;
+    }
+    let persistent = start.elapsed();
+
+    let start = Instant::now();
+    for timestamp in &times {
+        let mut decoder = open_decoder(&input, stream_idx)?;
+        decode_at(&mut input, &mut decoder, stream_idx, timestamp.av_time())?;
+    }
+    let per_capture = start.elapsed();
+
+    let per = |elapsed: Duration| elapsed / times.len().max(1) as u32;
+    println!("{} captures of {}", times.len(), path);
+    println!(
+        "  persistent decoder:   {:?} ({:?} per capture)",
+        persistent,
+        per(persistent)
+    );
+    println!(
+        "  decoder per capture:  {:?} ({:?} per capture)",
+        per_capture,
+        per(per_capture)
+    );
+    println!(
+        "  speedup:              {:.2}x",
+        per_capture.as_secs_f64() / persistent.as_secs_f64()
+    );
+    Ok(())
+}
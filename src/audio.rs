@@ -0,0 +1,317 @@
+//! Items for making sheets of audio files, which have no video to capture frames from.
+use std::path::{Path, PathBuf};
+
+use derivative::Derivative;
+use ffmpeg::{
+    codec::decoder::Audio as AudioDecoder,
+    filter::{self, Graph},
+    format::context::Input,
+    util::{
+        frame::{audio::Audio, video::Video},
+        media::Type as MediaType,
+    },
+    DictionaryRef, Rational,
+};
+use image::RgbImage;
+
+use crate::{
+    ffmpeg_ext::{cover_art, HasStartTime as _, LinkableGraph as _, ToImage as _},
+    time::{Clock, Timestamp},
+    util::Dimensions,
+    video::{input_opts, probe_duration},
+    waveform::{self, open_audio_decoder, render_waveform},
+    Error, Result,
+};
+
+/// The tags of an audio file that are shown in the header of its sheet.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tags {
+    title: Option<String>,
+    artist: Option<String>,
+    album: Option<String>,
+}
+
+impl Tags {
+    pub fn new(title: Option<&str>, artist: Option<&str>, album: Option<&str>) -> Self {
+        Self {
+            title: title.map(str::to_string),
+            artist: artist.map(str::to_string),
+            album: album.map(str::to_string),
+        }
+    }
+
+    /// Reads the tags from a file's or stream's metadata. Tag names are matched case-insensitively,
+    /// as they're upper case in some formats (e.g. Vorbis comments).
+    fn from_metadata(metadata: &DictionaryRef) -> Self {
+        Self::new(
+            metadata.get("title"),
+            metadata.get("artist"),
+            metadata.get("album"),
+        )
+    }
+
+    /// Fills in any tags that are missing with the ones from `other`.
+    fn or(self, other: Self) -> Self {
+        Self {
+            title: self.title.or(other.title),
+            artist: self.artist.or(other.artist),
+            album: self.album.or(other.album),
+        }
+    }
+}
+
+/// The lines of text at the top of an audio file's sheet: the file name, the artist and title, the
+/// album and then the duration along with the provided details of the audio.
+pub fn header_lines(
+    filename: &str,
+    tags: &Tags,
+    duration: Timestamp,
+    details: &str,
+) -> Vec<String> {
+    let mut lines = vec![filename.to_string()];
+    match (&tags.artist, &tags.title) {
+        (Some(artist), Some(title)) => lines.push(format!("{} - {}", artist, title)),
+        (Some(line), None) | (None, Some(line)) => lines.push(line.clone()),
+        (None, None) => (),
+    }
+    if let Some(album) = &tags.album {
+        lines.push(album.clone());
+    }
+    lines.push(format!("{}  {}", duration, details));
+    lines
+}
+
+/// The highest sample rate that audio is brought down to before its spectrogram is drawn.
+const MAX_SPECTROGRAM_SAMPLE_RATE: u32 = 11025;
+/// The lowest sample rate that a spectrogram is still worth drawing at.
+const MIN_SPECTROGRAM_SAMPLE_RATE: u32 = 2000;
+/// The most samples that the `showspectrumpic` filter is given. It keeps every sample until the end
+/// of the file, so this (along with mixing down to mono) is what limits how much memory it uses: an
+/// hour at the highest sample rate, which is about 160 MB.
+const MAX_SPECTROGRAM_SAMPLES: f64 = MAX_SPECTROGRAM_SAMPLE_RATE as f64 * 60.0 * 60.0;
+
+/// The sample rate to bring a file's audio down to so that all of it fits in
+/// [`MAX_SPECTROGRAM_SAMPLES`], or `None` if the file is too long for a spectrogram at any rate
+/// worth drawing.
+fn spectrogram_sample_rate(duration: Timestamp) -> Option<u32> {
+    let rate = (MAX_SPECTROGRAM_SAMPLES / duration.seconds().max(1.0)) as u32;
+    let rate = rate.min(MAX_SPECTROGRAM_SAMPLE_RATE);
+    (rate >= MIN_SPECTROGRAM_SAMPLE_RATE).then_some(rate)
+}
+
+fn create_spectrogram_graph(
+    decoder: &AudioDecoder,
+    time_base: Rational,
+    sample_rate: u32,
+    dimensions: &Dimensions,
+) -> Result<Graph> {
+    let mut graph = Graph::new();
+    graph.add(
+        &filter::find("abuffer").unwrap(),
+        "in",
+        &[
+            format!("time_base={}", time_base),
+            format!("sample_rate={}", decoder.rate()),
+            format!("sample_fmt={}", decoder.format().name()),
+            format!("channel_layout=0x{:x}", decoder.channel_layout().bits()),
+        ]
+        .join(":"),
+    )?;
+    graph.add(&filter::find("buffersink").unwrap(), "out", "")?;
+    graph.add(
+        &filter::find("aformat").unwrap(),
+        "mono",
+        "channel_layouts=mono",
+    )?;
+    graph.add(
+        &filter::find("aresample").unwrap(),
+        "resample",
+        &sample_rate.to_string(),
+    )?;
+    graph.add(
+        &filter::find("showspectrumpic").unwrap(),
+        "spectrum",
+        &format!("s={}:legend=0", dimensions),
+    )?;
+    graph.add(&filter::find("format").unwrap(), "rgb", "pix_fmts=rgb24")?;
+    graph.chain_link(&["in", "mono", "resample", "spectrum", "rgb", "out"])?;
+    graph.validate()?;
+    Ok(graph)
+}
+
+/// The information about an audio file needed to make a sheet for it.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct AudioInfo {
+    path: PathBuf,
+    /// The duration of the file in `AV_TIME_BASE` units.
+    duration: i64,
+    stream_idx: usize,
+    container_clock: Clock,
+    #[derivative(Debug = "ignore")]
+    input: Input,
+}
+
+impl AudioInfo {
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut input = ffmpeg::format::input_with_dictionary(&path, input_opts())?;
+        let stream_idx = input
+            .streams()
+            .best(MediaType::Audio)
+            .ok_or_else(|| Error::NoAudioStream {
+                path: path.as_ref().to_path_buf(),
+            })?
+            .index();
+        let duration = probe_duration(&mut input, stream_idx, &path)?;
+        Ok(Self {
+            path: path.as_ref().to_path_buf(),
+            duration,
+            stream_idx,
+            container_clock: Clock::container(input.start_time()),
+            input,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn duration(&self) -> Timestamp {
+        Timestamp::new(self.duration)
+    }
+
+    /// The file's embedded cover art; see [`cover_art`].
+    pub fn cover_art(&self) -> Option<Vec<u8>> {
+        cover_art(&self.input)
+    }
+
+    /// The file's tags. Some formats (e.g. Ogg) keep them on the stream instead of the file.
+    pub fn tags(&self) -> Tags {
+        let tags = Tags::from_metadata(&self.input.metadata());
+        match self.input.stream(self.stream_idx) {
+            Some(stream) => tags.or(Tags::from_metadata(&stream.metadata())),
+            None => tags,
+        }
+    }
+
+    /// A short description of the audio stream, e.g. `flac, 44100 Hz, 2 channels`.
+    pub fn details(&self) -> Result<String> {
+        let stream = self.input.stream(self.stream_idx).unwrap();
+        let decoder = open_audio_decoder(&stream)?;
+        Ok(format!(
+            "{}, {} Hz, {} channels",
+            decoder.id().name(),
+            decoder.rate(),
+            decoder.channels()
+        ))
+    }
+
+    /// Draws the peak level of the whole file.
+    pub fn waveform(&mut self, dimensions: &Dimensions) -> Result<RgbImage> {
+        let width = dimensions.width() as usize;
+        let levels = waveform::audio_levels(
+            &mut self.input,
+            &self.container_clock,
+            Timestamp::ZERO,
+            self.duration(),
+            width,
+        )?
+        .unwrap_or_else(|| vec![0.0; width]);
+        Ok(render_waveform(&levels, dimensions.height(), &[]))
+    }
+
+    /// Draws a spectrogram of the whole file using ffmpeg's `showspectrumpic` filter, which needs
+    /// to be given all of the audio before it draws anything. The audio is mixed down to mono and
+    /// resampled (see [`spectrogram_sample_rate`]) first to limit how much of it is kept in memory.
+    /// Returns `None` if the file is too long to draw a spectrogram of.
+    pub fn spectrogram(&mut self, dimensions: &Dimensions) -> Result<Option<RgbImage>> {
+        let Some(sample_rate) = spectrogram_sample_rate(self.duration()) else {
+            return Ok(None);
+        };
+        let stream = self.input.stream(self.stream_idx).unwrap();
+        let time_base = stream.time_base();
+        let mut decoder = open_audio_decoder(&stream)?;
+        let mut graph = create_spectrogram_graph(&decoder, time_base, sample_rate, dimensions)?;
+        let seek_to = self.container_clock.to_clock(Timestamp::ZERO);
+        self.input.seek(seek_to, ..seek_to)?;
+        let stream_idx = self.stream_idx;
+        let layout = decoder.channel_layout();
+        let mut frame = Audio::empty();
+        let mut send_frames = |decoder: &mut AudioDecoder, graph: &mut Graph| -> Result<()> {
+            while decoder.receive_frame(&mut frame).is_ok() {
+                frame.set_channel_layout(layout);
+                graph.get("in").unwrap().source().add(&frame)?;
+            }
+            Ok(())
+        };
+        for (s, packet) in self.input.packets() {
+            if s.index() != stream_idx || decoder.send_packet(&packet).is_err() {
+                continue;
+            }
+            send_frames(&mut decoder, &mut graph)?;
+        }
+        decoder.send_eof()?;
+        send_frames(&mut decoder, &mut graph)?;
+        graph.get("in").unwrap().source().flush()?;
+        let mut picture = Video::empty();
+        graph.get("out").unwrap().sink().frame(&mut picture)?;
+        picture
+            .to_rgb_image()
+            .map(Some)
+            .ok_or_else(|| Error::Other {
+                message: "Could not convert the spectrogram to an image".to_string(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_lines() {
+        let duration = Timestamp::from_seconds(95.5);
+        let details = "flac, 44100 Hz, 2 channels";
+        assert_eq!(
+            header_lines("song.flac", &Tags::default(), duration, details),
+            vec![
+                "song.flac".to_string(),
+                "00:01:35.500  flac, 44100 Hz, 2 channels".to_string()
+            ]
+        );
+        let tags = Tags::new(Some("Title"), Some("Artist"), Some("Album"));
+        assert_eq!(
+            header_lines("song.flac", &tags, duration, details),
+            vec![
+                "song.flac".to_string(),
+                "Artist - Title".to_string(),
+                "Album".to_string(),
+                "00:01:35.500  flac, 44100 Hz, 2 channels".to_string()
+            ]
+        );
+        let tags = Tags::new(Some("Title"), None, None);
+        assert_eq!(
+            header_lines("song.flac", &tags, duration, details)[1],
+            "Title"
+        );
+    }
+
+    #[test]
+    fn test_spectrogram_sample_rate() {
+        let hours = |hours: f64| Timestamp::from_seconds(hours * 60.0 * 60.0);
+        assert_eq!(spectrogram_sample_rate(Timestamp::ZERO), Some(11025));
+        assert_eq!(spectrogram_sample_rate(hours(0.5)), Some(11025));
+        assert_eq!(spectrogram_sample_rate(hours(2.0)), Some(5512));
+        assert_eq!(spectrogram_sample_rate(hours(20.0)), None);
+    }
+
+    #[test]
+    fn test_tags_or() {
+        let file = Tags::new(Some("Title"), None, None);
+        let stream = Tags::new(Some("Other"), Some("Artist"), None);
+        assert_eq!(
+            file.or(stream),
+            Tags::new(Some("Title"), Some("Artist"), None)
+        );
+    }
+}
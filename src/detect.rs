@@ -0,0 +1,217 @@
+//! Heuristics for finding the opening titles and end credits of a video, so that they can be left
+//! out of the captures.
+use image::RgbImage;
+
+use crate::{
+    time::Timestamp,
+    video::{ChapterInfo, VidInfo},
+    Result,
+};
+
+/// Chapter titles (in lower case) that mark the opening titles.
+const INTRO_CHAPTER_NAMES: &[&str] = &["intro", "opening", "op", "opening credits", "prologue"];
+/// Chapter titles (in lower case) that mark the end credits.
+const CREDITS_CHAPTER_NAMES: &[&str] = &["credits", "end credits", "ending", "ed", "outro"];
+/// The most of the capture range the opening titles can take up.
+const MAX_INTRO_FRACTION: f64 = 0.25;
+/// The most of the capture range the end credits can take up.
+const MAX_CREDITS_FRACTION: f64 = 0.3;
+/// How many frames to sample at each end of the capture range.
+const NUM_SAMPLES: usize = 12;
+/// Pixels with a luma below this are considered black.
+const DARK_LUMA: f64 = 40.0;
+/// The fraction of a frame's pixels that need to be black for it to be considered text on black.
+const DARK_FRACTION: f64 = 0.85;
+/// The most the average pixel can change between two samples for them to be considered low motion.
+const MAX_MOTION: f64 = 12.0;
+/// The fraction of the top of the frame that's analyzed, which leaves out the timestamp overlay.
+const ANALYZED_HEIGHT: f64 = 0.7;
+
+/// What part of a video something is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Segment {
+    Intro,
+    Credits,
+}
+
+fn classify_chapter(title: &str) -> Option<Segment> {
+    let title = title.trim().to_lowercase();
+    if INTRO_CHAPTER_NAMES.contains(&title.as_str()) {
+        Some(Segment::Intro)
+    } else if CREDITS_CHAPTER_NAMES.contains(&title.as_str()) {
+        Some(Segment::Credits)
+    } else {
+        None
+    }
+}
+
+#[inline]
+fn luma(pixel: &image::Rgb<u8>) -> f64 {
+    let [r, g, b] = pixel.0;
+    0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64
+}
+
+fn analyzed_rows(img: &RgbImage) -> u32 {
+    ((img.height() as f64 * ANALYZED_HEIGHT) as u32).max(1)
+}
+
+/// Whether the image is (mostly) black, like a title card or a credit roll.
+fn is_text_on_black(img: &RgbImage) -> bool {
+    let rows = analyzed_rows(img);
+    let total = (rows * img.width()) as f64;
+    let dark = img
+        .enumerate_pixels()
+        .filter(|(_, y, pixel)| *y < rows && luma(pixel) < DARK_LUMA)
+        .count();
+    total > 0.0 && dark as f64 / total >= DARK_FRACTION
+}
+
+/// The average difference in luma between each pixel of two images.
+fn motion(first: &RgbImage, second: &RgbImage) -> f64 {
+    if first.dimensions() != second.dimensions() {
+        return f64::MAX;
+    }
+    let rows = analyzed_rows(first);
+    let total = (rows * first.width()) as f64;
+    let diff: f64 = first
+        .enumerate_pixels()
+        .zip(second.pixels())
+        .filter(|((_, y, _), _)| *y < rows)
+        .map(|((_, _, a), b)| (luma(a) - luma(b)).abs())
+        .sum();
+    diff / total
+}
+
+/// Given frames sampled in order, returns how many of them (from the start) form a low-motion
+/// text-on-black segment.
+fn leading_static_dark_run(samples: &[RgbImage]) -> usize {
+    samples
+        .iter()
+        .enumerate()
+        .take_while(|(idx, img)| {
+            is_text_on_black(img) && (*idx == 0 || motion(&samples[idx - 1], img) <= MAX_MOTION)
+        })
+        .count()
+}
+
+fn sample_frames(info: &mut VidInfo, times: &[i64]) -> Result<Vec<RgbImage>> {
+    times
+        .iter()
+        .map(|timestamp| info.get_frame_at(Timestamp::new(*timestamp)))
+        .collect()
+}
+
+fn sample_times(start: i64, end: i64) -> Vec<i64> {
+    let step = (end - start) / NUM_SAMPLES as i64;
+    (0..NUM_SAMPLES as i64).map(|i| start + i * step).collect()
+}
+
+fn narrow_by_chapters(chapters: &[ChapterInfo], start: i64, end: i64) -> (i64, i64) {
+    let length = (end - start) as f64;
+    let mut range = (start, end);
+    for chapter in chapters {
+        let title = chapter.title().unwrap_or_default();
+        match classify_chapter(title) {
+            Some(Segment::Intro)
+                if chapter.end() <= start + (length * MAX_INTRO_FRACTION) as i64 =>
+            {
+                log::debug!("Found intro chapter \"{}\"", title);
+                range.0 = range.0.max(chapter.end());
+            }
+            Some(Segment::Credits)
+                if chapter.start() >= end - (length * MAX_CREDITS_FRACTION) as i64 =>
+            {
+                log::debug!("Found credits chapter \"{}\"", title);
+                range.1 = range.1.min(chapter.start());
+            }
+            _ => {}
+        }
+    }
+    range
+}
+
+/// Narrows the range of the video to capture so that it leaves out the opening titles and end
+/// credits. Chapter names are used when the video has them; otherwise the start and end of the
+/// range are sampled for long, low-motion, text-on-black segments.
+pub fn narrow_capture_range(info: &mut VidInfo, start: i64, end: i64) -> Result<(i64, i64)> {
+    let (mut new_start, mut new_end) = narrow_by_chapters(&info.chapters(), start, end);
+    let length = (end - start) as f64;
+    if new_start == start {
+        let intro_end = start + (length * MAX_INTRO_FRACTION) as i64;
+        let times = sample_times(start, intro_end);
+        let run = leading_static_dark_run(&sample_frames(info, &times)?);
+        // A single dark frame is more likely a fade than a title sequence.
+        if run > 1 {
+            new_start = times.get(run).copied().unwrap_or(intro_end);
+            log::debug!("Detected opening titles ending at {}", new_start);
+        }
+    }
+    if new_end == end {
+        let credits_start = end - (length * MAX_CREDITS_FRACTION) as i64;
+        let times = sample_times(credits_start, end);
+        // The frames are sampled in order, which sequential decoding needs to not start over for
+        // each one, and then looked at from the end backwards.
+        let mut samples = sample_frames(info, &times)?;
+        samples.reverse();
+        let run = leading_static_dark_run(&samples);
+        if run > 1 {
+            new_end = times[times.len() - run];
+            log::debug!("Detected end credits starting at {}", new_end);
+        }
+    }
+    if new_start >= new_end {
+        log::warn!("Intro and credits detection left nothing to capture; ignoring it.");
+        return Ok((start, end));
+    }
+    Ok((new_start, new_end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    fn text_on_black(text_rows: u32) -> RgbImage {
+        RgbImage::from_fn(20, 20, |_, y| {
+            if y < text_rows {
+                Rgb([255, 255, 255])
+            } else {
+                Rgb([0, 0, 0])
+            }
+        })
+    }
+
+    #[test]
+    fn test_classify_chapter() {
+        assert_eq!(classify_chapter("Intro"), Some(Segment::Intro));
+        assert_eq!(classify_chapter(" Opening "), Some(Segment::Intro));
+        assert_eq!(classify_chapter("End Credits"), Some(Segment::Credits));
+        assert_eq!(classify_chapter("Chapter 3"), None);
+    }
+
+    #[test]
+    fn test_is_text_on_black() {
+        assert!(is_text_on_black(&text_on_black(1)));
+        assert!(is_text_on_black(&text_on_black(2)));
+        assert!(!is_text_on_black(&text_on_black(10)));
+        assert!(!is_text_on_black(&RgbImage::from_pixel(
+            20,
+            20,
+            Rgb([128, 128, 128])
+        )));
+    }
+
+    #[test]
+    fn test_leading_static_dark_run() {
+        let bright = RgbImage::from_pixel(20, 20, Rgb([200, 200, 200]));
+        let samples = vec![
+            text_on_black(1),
+            text_on_black(1),
+            text_on_black(1),
+            bright.clone(),
+            text_on_black(1),
+        ];
+        assert_eq!(leading_static_dark_run(&samples), 3);
+        assert_eq!(leading_static_dark_run(&[bright]), 0);
+    }
+}
@@ -19,6 +19,14 @@ pub enum Error {
     NotAFile { path: PathBuf },
     #[snafu(display("File {} has no video stream.", path.display()))]
     NoVideoStream { path: PathBuf },
+    #[snafu(display("File {} has no audio stream.", path.display()))]
+    NoAudioStream { path: PathBuf },
+    #[snafu(display("File {} has no video stream matching {selector}.", path.display()))]
+    NoSuchStream { path: PathBuf, selector: String },
+    #[snafu(display("Could not determine the duration of {}.", path.display()))]
+    NoDuration { path: PathBuf },
+    #[snafu(display("Invalid time or frame number: {value}"))]
+    InvalidTime { value: String },
     #[snafu(display("Could not find filter named {filter_name}."))]
     NoSuchFilter { filter_name: String },
     #[snafu(transparent)]
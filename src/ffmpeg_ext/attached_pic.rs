@@ -0,0 +1,34 @@
+use ffmpeg::format::{
+    context::Input,
+    stream::{Disposition, Stream},
+};
+use std::slice;
+
+pub trait HasAttachedPicture {
+    /// The encoded image (usually a JPEG or PNG) of an attached picture stream, like the cover art
+    /// embedded in many MKV and MP4 files. `None` if the stream isn't an attached picture.
+    fn attached_picture(&self) -> Option<&[u8]>;
+}
+
+/// The embedded cover art (the first attached picture) of a file, still encoded, if it has any.
+pub fn cover_art(input: &Input) -> Option<Vec<u8>> {
+    input
+        .streams()
+        .find_map(|s| s.attached_picture().map(<[u8]>::to_vec))
+}
+
+impl HasAttachedPicture for Stream<'_> {
+    fn attached_picture(&self) -> Option<&[u8]> {
+        if !self.disposition().contains(Disposition::ATTACHED_PIC) {
+            return None;
+        }
+        unsafe {
+            let packet = &(*self.as_ptr()).attached_pic;
+            if packet.data.is_null() || packet.size <= 0 {
+                None
+            } else {
+                Some(slice::from_raw_parts(packet.data, packet.size as usize))
+            }
+        }
+    }
+}
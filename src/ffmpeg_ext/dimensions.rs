@@ -1,5 +1,7 @@
 use crate::util::Dimensions;
-use ffmpeg::{decoder::Video as VideoDecoder, util::frame::video::Video};
+use ffmpeg::{
+    codec::Parameters, decoder::Video as VideoDecoder, util::frame::video::Video, Rational,
+};
 
 pub trait HasDimensions {
     fn dimensions(&self) -> Dimensions;
@@ -13,14 +15,78 @@ pub trait HasWidthAndHeight: HasDimensions {
     }
 }
 
+/// The dimensions of a video with the provided storage dimensions and sample aspect ratio when it's
+/// displayed. The height is kept, and the width is stretched or squeezed to match the aspect ratio.
+/// An unknown (zero) or invalid sample aspect ratio is treated as square pixels.
+pub fn display_dimensions(storage: &Dimensions, sample_aspect_ratio: Rational) -> Dimensions {
+    if sample_aspect_ratio.numerator() <= 0 || sample_aspect_ratio.denominator() <= 0 {
+        return storage.clone();
+    }
+    let width = (storage.width() as f64 * sample_aspect_ratio.numerator() as f64
+        / sample_aspect_ratio.denominator() as f64)
+        .round() as u32;
+    Dimensions::new(width.max(1), storage.height())
+}
+
+pub trait HasDisplayDimensions: HasDimensions {
+    fn sample_aspect_ratio(&self) -> Rational;
+    /// The dimensions once the sample aspect ratio has been applied.
+    fn display_dimensions(&self) -> Dimensions {
+        display_dimensions(&self.dimensions(), self.sample_aspect_ratio())
+    }
+}
+
 impl HasDimensions for VideoDecoder {
     fn dimensions(&self) -> Dimensions {
         Dimensions::new(self.width(), self.height())
     }
 }
 
+impl HasDimensions for Parameters {
+    fn dimensions(&self) -> Dimensions {
+        unsafe {
+            Dimensions::new(
+                (*self.as_ptr()).width as u32,
+                (*self.as_ptr()).height as u32,
+            )
+        }
+    }
+}
+
 impl HasDimensions for Video {
     fn dimensions(&self) -> Dimensions {
         Dimensions::new(self.width(), self.height())
     }
 }
+
+impl HasDisplayDimensions for VideoDecoder {
+    fn sample_aspect_ratio(&self) -> Rational {
+        self.aspect_ratio()
+    }
+}
+
+impl HasDisplayDimensions for Video {
+    fn sample_aspect_ratio(&self) -> Rational {
+        self.aspect_ratio()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_dimensions() {
+        let ntsc = Dimensions::new(720, 480);
+        assert_eq!(
+            display_dimensions(&ntsc, Rational::new(32, 27)),
+            Dimensions::new(853, 480)
+        );
+        assert_eq!(
+            display_dimensions(&ntsc, Rational::new(8, 9)),
+            Dimensions::new(640, 480)
+        );
+        assert_eq!(display_dimensions(&ntsc, Rational::new(1, 1)), ntsc);
+        assert_eq!(display_dimensions(&ntsc, Rational::new(0, 1)), ntsc);
+    }
+}
@@ -0,0 +1,94 @@
+use ffmpeg::{format::Pixel, util::frame::video::Video};
+use image::{GrayImage, ImageBuffer, Pixel as ImagePixel, RgbImage, RgbaImage};
+
+/// Copies `rows` rows of `row_len` bytes out of a plane whose rows start `stride` bytes apart,
+/// leaving out the padding at the end of each row.
+pub fn copy_plane(data: &[u8], stride: usize, row_len: usize, rows: usize) -> Vec<u8> {
+    let mut packed = Vec::with_capacity(row_len * rows);
+    data.chunks(stride)
+        .take(rows)
+        .for_each(|row| packed.extend_from_slice(&row[..row_len]));
+    packed
+}
+
+fn to_image<P>(frame: &Video, format: Pixel) -> Option<ImageBuffer<P, Vec<u8>>>
+where
+    P: ImagePixel<Subpixel = u8>,
+{
+    if frame.format() != format || frame.width() == 0 {
+        return None;
+    }
+    let row_len = frame.width() as usize * P::CHANNEL_COUNT as usize;
+    let data = copy_plane(
+        frame.data(0),
+        frame.stride(0),
+        row_len,
+        frame.height() as usize,
+    );
+    ImageBuffer::from_raw(frame.width(), frame.height(), data)
+}
+
+/// Converts video frames into images. Each conversion only works if the frame is already in the
+/// matching pixel format, and returns `None` otherwise.
+pub trait ToImage {
+    /// Converts an `RGB24` frame.
+    fn to_rgb_image(&self) -> Option<RgbImage>;
+    /// Converts an `RGBA` frame.
+    fn to_rgba_image(&self) -> Option<RgbaImage>;
+    /// Converts a `GRAY8` frame.
+    fn to_luma_image(&self) -> Option<GrayImage>;
+}
+
+impl ToImage for Video {
+    fn to_rgb_image(&self) -> Option<RgbImage> {
+        to_image(self, Pixel::RGB24)
+    }
+
+    fn to_rgba_image(&self) -> Option<RgbaImage> {
+        to_image(self, Pixel::RGBA)
+    }
+
+    fn to_luma_image(&self) -> Option<GrayImage> {
+        to_image(self, Pixel::GRAY8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_plane() {
+        // Two rows of three bytes, each padded out to five.
+        let data = [1, 2, 3, 0, 0, 4, 5, 6, 0, 0];
+        assert_eq!(copy_plane(&data, 5, 3, 2), vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(copy_plane(&data, 5, 3, 1), vec![1, 2, 3]);
+        assert_eq!(copy_plane(&data[..3], 3, 3, 1), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_padded_frame_to_image() {
+        // Widths that aren't a multiple of the alignment leave padding at the end of each row.
+        let mut frame = Video::new(Pixel::RGB24, 5, 3);
+        assert!(frame.stride(0) > 15);
+        let stride = frame.stride(0);
+        for (y, row) in frame.data_mut(0).chunks_mut(stride).enumerate() {
+            row.fill(0xff);
+            row[..15].fill(y as u8);
+        }
+        let img = frame.to_rgb_image().unwrap();
+        assert_eq!(img.dimensions(), (5, 3));
+        assert!(img.enumerate_pixels().all(|(_, y, p)| p.0 == [y as u8; 3]));
+        assert!(frame.to_rgba_image().is_none());
+
+        let mut frame = Video::new(Pixel::GRAY8, 7, 2);
+        let stride = frame.stride(0);
+        frame.data_mut(0).chunks_mut(stride).for_each(|row| {
+            row.fill(0xff);
+            row[..7].fill(1);
+        });
+        let img = frame.to_luma_image().unwrap();
+        assert_eq!(img.dimensions(), (7, 2));
+        assert!(img.pixels().all(|p| p.0 == [1]));
+    }
+}
@@ -3,10 +3,21 @@ pub mod seek;
 pub use seek::Flags as SeekFlags;
 pub use seek::FrameSeekable;
 
+pub mod attached_pic;
+pub use attached_pic::cover_art;
+pub use attached_pic::HasAttachedPicture;
 pub mod coded_dim;
 pub use coded_dim::HasCodedDimensions;
 pub mod dimensions;
 pub use dimensions::HasDimensions;
+pub use dimensions::HasDisplayDimensions;
+pub mod frame;
+pub use frame::ToImage;
 pub mod filters;
 pub use filters::LinkableFilterContext;
 pub use filters::LinkableGraph;
+pub mod start_time;
+pub use start_time::HasStartTime;
+pub mod subtitle;
+pub use subtitle::IsBitmapSubtitle;
+pub use subtitle::ToRgbaImage;
@@ -0,0 +1,12 @@
+use ffmpeg::format::context::Context;
+
+pub trait HasStartTime {
+    /// The time of the first frame, in `AV_TIME_BASE` units, or `AV_NOPTS_VALUE` if it's unknown.
+    fn start_time(&self) -> i64;
+}
+
+impl HasStartTime for Context {
+    fn start_time(&self) -> i64 {
+        unsafe { (*self.as_ptr()).start_time }
+    }
+}
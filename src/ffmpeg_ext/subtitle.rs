@@ -0,0 +1,83 @@
+use ffmpeg::{codec::subtitle::Bitmap, codec::Id, ffi};
+use image::{Rgba, RgbaImage};
+use std::slice;
+
+/// Converts a paletted bitmap (one palette index per pixel, with rows `stride` bytes apart) into an
+/// image. The palette's colors are ARGB, as they are in ffmpeg's subtitle rectangles.
+pub fn palette_to_image(
+    indices: &[u8],
+    stride: usize,
+    width: u32,
+    height: u32,
+    palette: &[u32],
+) -> Option<RgbaImage> {
+    if stride < width as usize || indices.len() < stride * height.saturating_sub(1) as usize {
+        return None;
+    }
+    let mut img = RgbaImage::new(width, height);
+    for (y, row) in indices.chunks(stride).take(height as usize).enumerate() {
+        for (x, idx) in row.iter().take(width as usize).enumerate() {
+            let color = palette.get(*idx as usize).copied().unwrap_or(0);
+            let [a, r, g, b] = color.to_be_bytes();
+            img.put_pixel(x as u32, y as u32, Rgba([r, g, b, a]));
+        }
+    }
+    Some(img)
+}
+
+pub trait IsBitmapSubtitle {
+    /// Whether a subtitle codec produces pictures (like DVD and Blu-ray subtitles) instead of text.
+    fn is_bitmap_subtitle(&self) -> bool;
+}
+
+impl IsBitmapSubtitle for Id {
+    fn is_bitmap_subtitle(&self) -> bool {
+        unsafe {
+            let descriptor = ffi::avcodec_descriptor_get((*self).into());
+            !descriptor.is_null()
+                && ((*descriptor).props & ffi::AV_CODEC_PROP_BITMAP_SUB as i32) != 0
+        }
+    }
+}
+
+pub trait ToRgbaImage {
+    /// Converts the picture of a subtitle into an image, or `None` if it has no picture.
+    fn to_rgba_image(&self) -> Option<RgbaImage>;
+}
+
+impl ToRgbaImage for Bitmap<'_> {
+    fn to_rgba_image(&self) -> Option<RgbaImage> {
+        unsafe {
+            let rect = &*self.as_ptr();
+            if rect.data[0].is_null() || rect.data[1].is_null() || rect.w <= 0 || rect.h <= 0 {
+                return None;
+            }
+            let stride = rect.linesize[0] as usize;
+            let indices = slice::from_raw_parts(rect.data[0], stride * rect.h as usize);
+            let palette = slice::from_raw_parts(
+                rect.data[1] as *const u32,
+                rect.nb_colors.clamp(0, 256) as usize,
+            );
+            palette_to_image(indices, stride, rect.w as u32, rect.h as u32, palette)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette_to_image() {
+        let palette = [0x00000000, 0xFFFF0000, 0x8000FF00];
+        // A 2x2 bitmap with a byte of padding at the end of each row.
+        let indices = [0, 1, 9, 2, 1, 9];
+        let img = palette_to_image(&indices, 3, 2, 2, &palette).unwrap();
+        assert_eq!(img.dimensions(), (2, 2));
+        assert_eq!(*img.get_pixel(0, 0), Rgba([0, 0, 0, 0]));
+        assert_eq!(*img.get_pixel(1, 0), Rgba([255, 0, 0, 255]));
+        assert_eq!(*img.get_pixel(0, 1), Rgba([0, 255, 0, 128]));
+        assert_eq!(*img.get_pixel(1, 1), Rgba([255, 0, 0, 255]));
+        assert!(palette_to_image(&indices, 1, 2, 2, &palette).is_none());
+    }
+}
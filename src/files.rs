@@ -44,6 +44,53 @@ pub fn img_file_name<P: AsRef<Path>>(path: &P) -> String {
     //format!("{}.webp", get_filename(path))
 }
 
+/// Gets the file name of the sidecar file listing the times to capture for a video, which is simply
+/// the file name suffixed with `.timestamps`.
+pub fn timestamps_file_name<P: AsRef<Path>>(path: &P) -> String {
+    format!("{}.timestamps", get_filename(path))
+}
+
+/// The extensions of the subtitle files that are read from next to a video, most preferred first.
+const SUBTITLE_EXTENSIONS: [&str; 3] = ["srt", "ass", "ssa"];
+
+/// Gets the paths of the subtitle files that could be next to a video, most preferred first.
+/// Subtitle files are named after the video without its extension, optionally with a language code
+/// before their own extension (e.g. `video.srt` or `video.eng.srt` for `video.mkv`).
+pub fn subtitle_sidecar_paths<P: AsRef<Path>>(path: P, language: Option<&str>) -> Vec<PathBuf> {
+    let path = path.as_ref();
+    let stem = get_file_stem(path);
+    let mut names = Vec::new();
+    if let Some(language) = language {
+        names.extend(
+            SUBTITLE_EXTENSIONS
+                .iter()
+                .map(|ext| format!("{}.{}.{}", stem, language, ext)),
+        );
+    }
+    names.extend(
+        SUBTITLE_EXTENSIONS
+            .iter()
+            .map(|ext| format!("{}.{}", stem, ext)),
+    );
+    names
+        .into_iter()
+        .map(|name| path.with_file_name(name))
+        .collect()
+}
+
+/// The suffix (before the extension) of the files that a video's cover art is extracted to.
+const COVER_ART_SUFFIX: &str = ".cover";
+
+/// Gets the file name to extract a video's cover art to, which is the file name suffixed with
+/// `.cover` and the image's extension.
+pub fn cover_art_file_name<P: AsRef<Path>>(path: &P, extension: &str) -> String {
+    format!("{}{}.{}", get_filename(path), COVER_ART_SUFFIX, extension)
+}
+
+fn is_cover_art_file<P: AsRef<Path>>(path: P) -> bool {
+    get_file_stem(path).ends_with(COVER_ART_SUFFIX)
+}
+
 #[derive(Debug)]
 struct FileInfo {
     video: Option<PathBuf>,
@@ -217,8 +264,17 @@ where
     rval > 0
 }
 
+fn audio_mime_type_filter<P>(path: P) -> bool
+where
+    P: AsRef<Path>,
+{
+    mime_guess::from_path(path)
+        .into_iter()
+        .any(|g| g.type_() == mime::AUDIO)
+}
+
 /// Gets a list of video files to process. A video file should be processed if:
-/// 1. It has the MIME type of `video/*`.
+/// 1. It has the MIME type of `video/*` (or `audio/*`, if audio files are enabled).
 /// 2. It doesn't already have a screencap file for it.
 /// 3. If it does have a screencap file for it, then the video file must have been modified more
 ///    recently than the screencap file.
@@ -243,12 +299,16 @@ pub fn get_video_files_to_process(settings: &Settings) -> Result<Vec<PathBuf>> {
         })
         .filter(|p| p.exists())
         .filter(|p| !ignorer.should_ignore(p))
-        .filter(|p| video_mime_type_filter(p))
+        .filter(|p| {
+            video_mime_type_filter(p) || (settings.audio_files() && audio_mime_type_filter(p))
+        })
         .collect();
     video_files.iter().for_each(|p| files.add_video(p));
     log::info!("Finding existing screencaps...");
     read_dir(settings.out_dir())?
         .map(|f| f.unwrap().path())
+        // Extracted cover art lives alongside the screencaps, but isn't one.
+        .filter(|p| !is_cover_art_file(p))
         .for_each(|p| files.add_screencap(&p, &video_files));
     if !settings.keep_files() {
         let to_delete = files.get_screens_to_delete();
@@ -357,6 +417,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_subtitle_sidecar_paths() {
+        let video = PathBuf::from("/videos/show.s01e01.mkv");
+        assert_eq!(
+            subtitle_sidecar_paths(&video, None),
+            vec![
+                PathBuf::from("/videos/show.s01e01.srt"),
+                PathBuf::from("/videos/show.s01e01.ass"),
+                PathBuf::from("/videos/show.s01e01.ssa"),
+            ]
+        );
+        let paths = subtitle_sidecar_paths(&video, Some("eng"));
+        assert_eq!(paths.len(), 6);
+        assert_eq!(paths[0], PathBuf::from("/videos/show.s01e01.eng.srt"));
+        assert_eq!(paths[3], PathBuf::from("/videos/show.s01e01.srt"));
+    }
+
     #[test]
     fn test_create_video_mime_type_filter() {
         let test_vec_1 = vec!["file1.txt", "file2.html", "file3.txt"];
@@ -381,4 +458,15 @@ mod tests {
             .count();
         assert_eq!(results_3, 3);
     }
+
+    #[test]
+    fn test_audio_mime_type_filter() {
+        let files = vec!["file1.mp3", "file2.mp4", "file3.flac", "file4.txt"];
+        let results = files
+            .iter()
+            .map(PathBuf::from)
+            .filter(|p| audio_mime_type_filter(p))
+            .count();
+        assert_eq!(results, 2);
+    }
 }
@@ -1,13 +1,19 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 extern crate ffmpeg_next as ffmpeg;
 
+pub mod audio;
+pub mod detect;
 pub mod ffmpeg_ext;
 pub mod files;
 //pub mod opts;
 pub mod screencaps;
 pub mod settings;
+pub mod subtitles;
+pub mod text;
+pub mod time;
 pub mod util;
 pub mod video;
+pub mod waveform;
 
 pub mod cli;
 mod error;
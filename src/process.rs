@@ -1,7 +1,7 @@
 use crate::{
     cli::{self, MultiProgressExt as _},
     files, screencaps,
-    settings::Settings,
+    settings::{Parallelism, Settings},
     util::ENV,
     Result,
 };
@@ -42,18 +42,18 @@ pub fn rayon_process_videos(settings: &Settings, video_files: Vec<PathBuf>) -> R
     let items = video_files.into_iter().zip(iter::from_fn(|| {
         Some(mp.new_default_progress_bar(settings))
     }));
-    if settings.synchronous() {
+    if let Some(threads) = settings.threads() {
+        ThreadPoolBuilder::new()
+            .num_threads(threads as usize)
+            .build_global()
+            .unwrap();
+    }
+    if settings.synchronous() || settings.parallelism() == Parallelism::Captures {
         //items.try_for_each(|(path, pbar)| process_video(&pbar, settings, &path))
         items
             .map(|(path, pbar)| process_video(&pbar, settings, &path))
             .collect::<Result<Vec<_>>>()
     } else {
-        if let Some(threads) = settings.threads() {
-            ThreadPoolBuilder::new()
-                .num_threads(threads as usize)
-                .build_global()
-                .unwrap();
-        }
         items
             .par_bridge()
             .map(|(path, pbar)| process_video(&pbar, settings, &path))
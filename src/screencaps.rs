@@ -2,48 +2,100 @@
 use derivative::Derivative;
 use eyre::Result;
 use ffmpeg::format::Pixel;
-use image::{imageops, ImageFormat, RgbImage};
+use image::{imageops, imageops::FilterType, ImageFormat, RgbImage};
 use indicatif::ProgressBar;
+use rayon::prelude::*;
 #[cfg(target_family = "unix")]
 use std::os::unix::fs::symlink;
 #[cfg(target_family = "windows")]
 use std::os::windows::fs::symlink_dir as symlink;
 use std::{
+    collections::HashMap,
     fs::{self, DirBuilder},
     path::{Path, PathBuf},
 };
 use unicode_width::UnicodeWidthStr;
 
 use crate::{
-    files::get_filename,
-    settings::Settings,
-    util::{safe_string_truncate, sync_mtimes, Dimensions, ENV},
-    video::VidInfo,
+    audio::{header_lines, AudioInfo},
+    files::{cover_art_file_name, get_filename, img_file_name},
+    settings::{AudioVisualization, SeekMode, Settings},
+    text::render_text,
+    time::{self, Timestamp},
+    util::{mean_luma, perceptual_hash, safe_string_truncate, sync_mtimes, Dimensions, ENV},
+    video::{chapter_at, VidInfo},
+    waveform::{render_waveform, tick_position},
+    Error,
 };
 
 const MAX_DISPLAY_NAME_WIDTH: usize = 80;
+/// The most bits two captures' perceptual hashes can differ by for them to be considered duplicates.
+const MAX_DUPLICATE_HASH_DISTANCE: u32 = 2;
+/// The most two captures' average luma can differ by for them to be considered duplicates. Flat
+/// frames (like a fade to black and a white flash) all have the same hash, so this tells them apart.
+const MAX_DUPLICATE_LUMA_DIFFERENCE: f64 = 8.0;
 
 #[derive(Derivative)]
 #[derivative(Debug)]
 /// A representation of a single screen capture.
 pub struct ScreenCap {
-    timestamp: i64,
+    timestamp: Timestamp,
     dimensions: Dimensions,
     pixel_format: Pixel,
+    /// A perceptual hash of the image, used to find duplicate captures.
+    hash: u64,
+    /// The average luma of the image, also used to find duplicate captures.
+    luma: f64,
     #[derivative(Debug = "ignore")]
     image: RgbImage,
 }
 
 impl ScreenCap {
-    pub fn new(timestamp: i64, info: &mut VidInfo) -> Result<Self> {
-        let (dimensions, frame_data) = info.get_frame_at(timestamp)?;
-        let img = RgbImage::from_raw(dimensions.width(), dimensions.height(), frame_data).unwrap();
-        Ok(Self {
+    pub fn new(timestamp: Timestamp, info: &mut VidInfo) -> Result<Self> {
+        let seek_mode = info.seek_mode();
+        Self::with_seek_mode(timestamp, info, seek_mode)
+    }
+
+    /// Creates a screen capture using a specific seek mode instead of the video's current one.
+    pub fn with_seek_mode(
+        timestamp: Timestamp,
+        info: &mut VidInfo,
+        seek_mode: SeekMode,
+    ) -> Result<Self> {
+        let img = info.get_frame_with(timestamp, seek_mode)?;
+        Ok(Self::from_image(
+            timestamp,
+            info.capture_dimensions().clone(),
+            info.pixel_format(),
+            img,
+        ))
+    }
+
+    fn from_image(
+        timestamp: Timestamp,
+        dimensions: Dimensions,
+        pixel_format: Pixel,
+        image: RgbImage,
+    ) -> Self {
+        Self {
             timestamp,
-            dimensions: info.capture_dimensions().clone(),
-            pixel_format: info.pixel_format(),
-            image: img,
-        })
+            dimensions,
+            pixel_format,
+            hash: perceptual_hash(&image),
+            luma: mean_luma(&image),
+            image,
+        }
+    }
+
+    /// Whether this capture looks the same as another one.
+    pub fn is_duplicate_of(&self, other: &ScreenCap) -> bool {
+        (self.hash ^ other.hash).count_ones() <= MAX_DUPLICATE_HASH_DISTANCE
+            && (self.luma - other.luma).abs() <= MAX_DUPLICATE_LUMA_DIFFERENCE
+    }
+
+    /// The timestamp the capture was taken at.
+    pub fn timestamp(&self) -> Timestamp {
+        self.timestamp
     }
 
     /// The underlying image.
@@ -61,10 +113,6 @@ impl ScreenCap {
         self.dimensions.height()
     }
 
-    fn thumbnail(&self) -> RgbImage {
-        imageops::thumbnail(&self.image, self.width(), self.height())
-    }
-
     /// Saves the generated screen capture to the provided file.
     pub fn save_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
         log::info!("Saving to file {}", path.as_ref().display());
@@ -102,6 +150,63 @@ where
     Ok(())
 }
 
+/// Saves a video's cover art next to its screencap file, in whichever format it was embedded as.
+fn save_cover_art(settings: &Settings, vidfile: &Path, cover: &[u8]) -> Result<()> {
+    let extension = image::guess_format(cover)
+        .ok()
+        .and_then(|format| format.extensions_str().first().copied())
+        .unwrap_or("bin");
+    let mut out_path = settings.out_dir().to_path_buf();
+    out_path.push(cover_art_file_name(&vidfile, extension));
+    log::info!("Saving cover art to {}", out_path.display());
+    fs::write(&out_path, cover)?;
+    sync_mtimes(vidfile, out_path)?;
+    Ok(())
+}
+
+/// Saves a file's cover art, if the settings ask for it to be extracted.
+fn extract_cover_art(settings: &Settings, path: &Path, cover: Option<&[u8]>) -> Result<()> {
+    if !settings.extract_cover_art() {
+        return Ok(());
+    }
+    match cover {
+        Some(cover) => save_cover_art(settings, path, cover),
+        None => {
+            log::debug!("{} has no cover art to extract", get_filename(&path));
+            Ok(())
+        }
+    }
+}
+
+/// Decodes a video's cover art and fits it inside a tile of the provided size, centered on black.
+fn cover_art_tile(cover: &[u8], dimensions: &Dimensions) -> Option<RgbImage> {
+    let art = match image::load_from_memory(cover) {
+        Ok(art) => art.to_rgb8(),
+        Err(e) => {
+            log::warn!("Could not decode cover art: {}", e);
+            return None;
+        }
+    };
+    let scale = f64::min(
+        dimensions.width() as f64 / art.width() as f64,
+        dimensions.height() as f64 / art.height() as f64,
+    );
+    let art = imageops::resize(
+        &art,
+        ((art.width() as f64 * scale) as u32).max(1),
+        ((art.height() as f64 * scale) as u32).max(1),
+        FilterType::Lanczos3,
+    );
+    let mut tile = RgbImage::new(dimensions.width(), dimensions.height());
+    imageops::replace(
+        &mut tile,
+        &art,
+        ((dimensions.width() - art.width()) / 2) as i64,
+        ((dimensions.height() - art.height()) / 2) as i64,
+    );
+    Some(tile)
+}
+
 /// Returns the path of the image we should link to instead, or `None` is there is no such image
 /// file.
 fn get_image_to_link_to<P>(settings: &Settings, video_file: P) -> Option<PathBuf>
@@ -142,6 +247,320 @@ where
     Ok(())
 }
 
+/// The number of rows needed to lay out `num_captures` captures. Note that the first row holds one
+/// more capture than the others (see [`Settings::num_captures`]).
+fn rows_needed(settings: &Settings, num_captures: usize) -> u32 {
+    (num_captures.saturating_sub(2) as u32) / settings.columns() + 1
+}
+
+/// Shortens a caption to fit on the one line that [`render_text`] draws, assuming that characters
+/// are about a third as wide as the caption is tall.
+fn fit_caption(caption: &str, dimensions: &Dimensions) -> String {
+    let max_width = (dimensions.width() * 3 / dimensions.height().max(1)) as usize;
+    if caption.width() > max_width {
+        format!(
+            "{}...",
+            safe_string_truncate(caption, max_width.saturating_sub(3))
+        )
+    } else {
+        caption.to_string()
+    }
+}
+
+/// Something that frames are captured from: a video, or a stand-in for one in the tests.
+trait CaptureSource {
+    fn seek_mode(&self) -> SeekMode;
+    fn path(&self) -> &Path;
+    fn capture(&mut self, timestamp: Timestamp, seek_mode: SeekMode) -> Result<ScreenCap>;
+}
+
+impl CaptureSource for VidInfo {
+    fn seek_mode(&self) -> SeekMode {
+        VidInfo::seek_mode(self)
+    }
+
+    fn path(&self) -> &Path {
+        VidInfo::path(self)
+    }
+
+    fn capture(&mut self, timestamp: Timestamp, seek_mode: SeekMode) -> Result<ScreenCap> {
+        ScreenCap::with_seek_mode(timestamp, self, seek_mode)
+    }
+}
+
+/// The times and seek modes to retry a duplicate capture with: first the same time with accurate
+/// seeking (when fast seeking is in use), and then the nudged time.
+fn duplicate_retries(
+    seek_mode: SeekMode,
+    timestamp: Timestamp,
+    nudged: Timestamp,
+) -> Vec<(Timestamp, SeekMode)> {
+    match seek_mode {
+        SeekMode::Fast => vec![
+            (timestamp, SeekMode::Accurate),
+            (nudged, SeekMode::Accurate),
+        ],
+        // Keyframe-only seeking is usually chosen for speed, so it's kept when nudging.
+        mode => vec![(nudged, mode)],
+    }
+}
+
+/// Tries to replace a capture that duplicates the one before it: first by capturing the same time
+/// again with accurate seeking (when fast seeking is in use), and then by nudging the time halfway
+/// towards the next capture.
+/// If the duplicate can't be avoided, then the original capture is kept.
+fn avoid_duplicate<S: CaptureSource>(
+    info: &mut S,
+    previous: &ScreenCap,
+    capture: ScreenCap,
+    next_timestamp: Option<Timestamp>,
+) -> Result<ScreenCap> {
+    let timestamp = capture.timestamp();
+    let nudged = match next_timestamp {
+        Some(next) => timestamp.av_time() + (next.av_time() - timestamp.av_time()) / 2,
+        None => timestamp.av_time() + (timestamp.av_time() - previous.timestamp().av_time()) / 2,
+    };
+    let nudged = Timestamp::new(nudged);
+    for (retry_timestamp, seek_mode) in duplicate_retries(info.seek_mode(), timestamp, nudged) {
+        log::debug!(
+            "Capture at {} of {} is a duplicate; retrying at {} with {:?} seeking",
+            timestamp,
+            info.path().display(),
+            retry_timestamp,
+            seek_mode
+        );
+        let retry = info.capture(retry_timestamp, seek_mode)?;
+        if !retry.is_duplicate_of(previous) {
+            return Ok(retry);
+        }
+    }
+    log::warn!(
+        "Could not avoid a duplicate capture at {} of {}",
+        timestamp,
+        info.path().display()
+    );
+    Ok(capture)
+}
+
+fn capture_chunk<S: CaptureSource>(
+    pbar: &ProgressBar,
+    info: &mut S,
+    times: &[Timestamp],
+) -> Result<Vec<ScreenCap>> {
+    let mut captures: Vec<ScreenCap> = Vec::with_capacity(times.len());
+    for (idx, timestamp) in times.iter().enumerate() {
+        log::trace!(
+            "Generating screencap for {} at time {}",
+            info.path().display(),
+            timestamp
+        );
+        let seek_mode = info.seek_mode();
+        let mut capture = info.capture(*timestamp, seek_mode)?;
+        if let Some(previous) = captures.last() {
+            if capture.is_duplicate_of(previous) {
+                capture = avoid_duplicate(info, previous, capture, times.get(idx + 1).copied())?;
+            }
+        }
+        captures.push(capture);
+        pbar.inc(1);
+    }
+    Ok(captures)
+}
+
+/// Captures the frames at each of the provided times, in order. If the settings allow more than
+/// one capture worker, then the times are split between that many independent decoders on the
+/// rayon thread pool.
+fn capture_frames(
+    pbar: &ProgressBar,
+    settings: &Settings,
+    path: &Path,
+    info: &mut VidInfo,
+    times: &[Timestamp],
+) -> Result<Vec<ScreenCap>> {
+    let workers = settings.capture_workers().min(times.len());
+    if workers <= 1 {
+        return capture_chunk(pbar, info, times);
+    }
+    log::debug!(
+        "Splitting {} captures between {} workers for {}",
+        times.len(),
+        workers,
+        path.display()
+    );
+    let chunk_len = times.len().div_ceil(workers);
+    let mut worker_infos = (0..times.len().div_ceil(chunk_len))
+        .map(|_| info.worker(settings))
+        .collect::<Result<Vec<_>>>()?;
+    let chunks = times
+        .par_chunks(chunk_len)
+        .zip(worker_infos.par_iter_mut())
+        .map(|(chunk, worker)| capture_chunk(pbar, worker, chunk))
+        .collect::<Result<Vec<_>>>()?;
+    // Each worker only checks its captures against the others in its chunk, so the first capture
+    // of each chunk still needs to be checked against the last one of the chunk before it.
+    let mut captures: Vec<ScreenCap> = Vec::with_capacity(times.len());
+    for chunk in chunks {
+        let start = captures.len();
+        for capture in chunk {
+            let capture = match captures.last() {
+                Some(previous) if captures.len() == start && capture.is_duplicate_of(previous) => {
+                    avoid_duplicate(info, previous, capture, times.get(start + 1).copied())?
+                }
+                _ => capture,
+            };
+            captures.push(capture);
+        }
+    }
+    Ok(captures)
+}
+
+/// Adds a strip beneath the grid showing the audio between the first and last captures, with a
+/// tick mark at the time of each capture. The grid is returned unchanged if there's no audio.
+fn add_waveform_strip(
+    info: &mut VidInfo,
+    grid: RgbImage,
+    captures: &[ScreenCap],
+    cap_height: u32,
+) -> Result<RgbImage> {
+    let (Some(first), Some(last)) = (captures.first(), captures.last()) else {
+        return Ok(grid);
+    };
+    let (start, end) = (first.timestamp(), last.timestamp());
+    let Some(levels) = info.audio_levels(start, end, grid.width() as usize)? else {
+        log::debug!("No audio to draw a waveform for");
+        return Ok(grid);
+    };
+    let ticks: Vec<u32> = captures
+        .iter()
+        .map(|c| tick_position(c.timestamp(), start, end, grid.width()))
+        .collect();
+    let strip = render_waveform(&levels, (cap_height / 2).max(16), &ticks);
+    let mut img = RgbImage::new(grid.width(), grid.height() + strip.height());
+    imageops::replace(&mut img, &grid, 0, 0);
+    imageops::replace(&mut img, &strip, 0, grid.height() as i64);
+    Ok(img)
+}
+
+/// Generates a sheet showing each of the video's streams side by side, with one row per capture
+/// time, and saves it. Each stream is opened from `info`, so that its subtitles are shared. The
+/// waveform is drawn beneath the grid as usual, but captions, chapter labels and the cover art tile
+/// don't fit this layout and are left out.
+fn generate_multi_stream(
+    pbar: &ProgressBar,
+    settings: &Settings,
+    path: &Path,
+    info: &VidInfo,
+    streams: &[usize],
+    out_path: PathBuf,
+) -> Result<()> {
+    let left_out: Vec<&str> = [
+        (settings.subtitle_captions(), "subtitle captions"),
+        (settings.chapter_labels(), "chapter labels"),
+        (settings.cover_art_tile(), "the cover art tile"),
+    ]
+    .into_iter()
+    .filter_map(|(enabled, name)| enabled.then_some(name))
+    .collect();
+    if !left_out.is_empty() {
+        log::warn!(
+            "Leaving {} out of the sheet for {}, since it shows all of its video streams side by \
+             side.",
+            left_out.join(", "),
+            path.display()
+        );
+    }
+    let stream_settings = settings.with_layout(streams.len() as u32, settings.rows());
+    let mut infos = streams
+        .iter()
+        .map(|idx| info.other_stream(&stream_settings, *idx))
+        .collect::<Result<Vec<_>>>()?;
+    let times = match time::explicit_capture_times(settings, path)? {
+        Some(times) => infos[0].resolve_capture_times(&times)?,
+        None => infos[0].generate_n_capture_times(settings, settings.rows() as usize)?,
+    };
+    log::trace!(
+        "Capturing {} streams at {} times for {}",
+        infos.len(),
+        times.len(),
+        path.display()
+    );
+    pbar.set_length((times.len() * infos.len() + 2) as u64);
+    // The streams can have different aspect ratios, so each row is as tall as the tallest one.
+    let row_height = infos
+        .iter()
+        .map(|info| info.capture_dimensions().height() + 2)
+        .max()
+        .unwrap_or_default();
+    let width = infos
+        .iter()
+        .map(|info| info.capture_dimensions().width() + 2)
+        .sum();
+    let mut img = RgbImage::new(width, row_height * times.len() as u32);
+    let mut current_x = 1;
+    let mut first_captures = None;
+    for info in infos.iter_mut() {
+        let captures = capture_frames(pbar, &stream_settings, path, info, &times)?;
+        for (row, capture) in captures.iter().enumerate() {
+            let current_y = (row as u32 * row_height + 1) as i64;
+            imageops::replace(&mut img, capture.image(), current_x, current_y);
+        }
+        current_x += (info.capture_dimensions().width() + 2) as i64;
+        first_captures.get_or_insert(captures);
+    }
+    if settings.waveform() {
+        if let Some(captures) = &first_captures {
+            img = add_waveform_strip(&mut infos[0], img, captures, row_height)?;
+        }
+    }
+    img.save_with_format(out_path.clone(), ImageFormat::Jpeg)?;
+    finish_generation(pbar, path, out_path)
+}
+
+/// Generates a sheet for an audio file and saves it. The sheet has the file's tags at the top,
+/// followed by its cover art (if it has any) and then a visualization of the whole file.
+fn generate_audio(pbar: &ProgressBar, settings: &Settings, path: &Path) -> Result<()> {
+    let filename = get_filename(&path);
+    let mut info = AudioInfo::new(path)?;
+    pbar.set_length(3);
+    pbar.inc(1);
+    let cover = info.cover_art();
+    extract_cover_art(settings, path, cover.as_deref())?;
+    let width = settings.width();
+    let line_dims = Dimensions::new(width, (width / 40).max(16));
+    let mut parts = Vec::new();
+    for line in header_lines(&filename, &info.tags(), info.duration(), &info.details()?) {
+        parts.push(render_text(&line_dims, &line)?);
+    }
+    if let Some(tile) = cover.and_then(|c| cover_art_tile(&c, &Dimensions::new(width, width / 2))) {
+        parts.push(tile);
+    }
+    let visualization_dims = Dimensions::new(width, width / 4);
+    parts.push(match settings.audio_visualization() {
+        AudioVisualization::Spectrogram => match info.spectrogram(&visualization_dims)? {
+            Some(spectrogram) => spectrogram,
+            None => {
+                log::warn!(
+                    "{} is too long to draw a spectrogram of; drawing a waveform instead.",
+                    filename
+                );
+                info.waveform(&visualization_dims)?
+            }
+        },
+        AudioVisualization::Waveform => info.waveform(&visualization_dims)?,
+    });
+    pbar.inc(1);
+    let mut img = RgbImage::new(width, parts.iter().map(|p| p.height() + 2).sum());
+    let mut current_y = 1;
+    for part in &parts {
+        imageops::replace(&mut img, part, 0, current_y);
+        current_y += (part.height() + 2) as i64;
+    }
+    let mut out_path = settings.out_dir().to_path_buf();
+    out_path.push(img_file_name(&path));
+    img.save_with_format(out_path.clone(), ImageFormat::Jpeg)?;
+    finish_generation(pbar, path, out_path)
+}
+
 /// Generates the screencap for a file and saves it.
 pub fn generate<P>(pbar: &ProgressBar, settings: &Settings, path: P) -> Result<()>
 where
@@ -159,8 +578,16 @@ where
     };
     pbar.set_message(display_name);
     log::debug!("Getting video info for {}", filename);
-    let mut info = VidInfo::new(settings, &path)?;
+    let mut info = match VidInfo::new(settings, &path) {
+        Err(Error::NoVideoStream { .. }) if settings.audio_files() => {
+            log::debug!("{} has no video, so treating it as an audio file", filename);
+            return generate_audio(pbar, settings, path.as_ref());
+        }
+        info => info?,
+    };
     pbar.inc(1);
+    let cover = info.cover_art();
+    extract_cover_art(settings, path.as_ref(), cover.as_deref())?;
     let mut out_path = settings.out_dir().to_path_buf();
     out_path.push(info.img_file_name());
     log::info!("Searching for image to link to for file {}", filename);
@@ -177,40 +604,216 @@ where
             return finish_generation(pbar, path, out_path);
         }
     }
-    log::trace!("Generating capture times for {}", filename);
-    let times = info.generate_capture_times(settings);
+    if settings.all_video_streams() {
+        let streams = info.video_stream_indices();
+        if streams.len() > 1 {
+            return generate_multi_stream(pbar, settings, path.as_ref(), &info, &streams, out_path);
+        }
+        log::debug!("{} only has one video stream", filename);
+    }
+    let hero_tile = match &cover {
+        Some(cover) if settings.cover_art_tile() => {
+            cover_art_tile(cover, info.capture_dimensions())
+        }
+        _ => None,
+    };
+    // The cover art takes up the first tile, and the captures follow it.
+    let first_tile = hero_tile.is_some() as usize;
+    let times = match time::explicit_capture_times(settings, &path)? {
+        Some(times) => {
+            log::trace!(
+                "Using {} explicit capture times for {}",
+                times.len(),
+                filename
+            );
+            info.resolve_capture_times(&times)?
+        }
+        None => {
+            log::trace!("Generating capture times for {}", filename);
+            info.generate_n_capture_times(settings, settings.num_captures() as usize - first_tile)?
+        }
+    };
     log::trace!("Generated {} capture times for {}", times.len(), filename);
+    pbar.set_length((times.len() + 2) as u64);
     let Dimensions(cap_width, cap_height) = info.capture_dimensions().clone();
+    let captions = if settings.subtitle_captions() {
+        info.caption_subtitles(settings.subtitle_language())?
+    } else {
+        None
+    };
+    // Each row leaves room beneath the captures for their captions.
+    let caption_dims = Dimensions::new(
+        cap_width,
+        captions.as_ref().map_or(0, |_| (cap_height / 8).max(12)),
+    );
+    let row_height = cap_height + caption_dims.height() + 2;
     let mut img = RgbImage::new(
         cap_width * settings.columns(),
-        (cap_height + 2) * settings.rows(),
+        row_height * rows_needed(settings, times.len() + first_tile),
     );
+    let chapters = if settings.chapter_labels() {
+        info.chapters()
+    } else {
+        Vec::new()
+    };
+    let mut chapter_labels = HashMap::new();
     let mut current_x = 1;
     let mut current_y = 1;
-    let captures = times
-        .iter()
-        .inspect(|timestamp| {
-            log::trace!(
-                "Generating screencap for {} at time {}",
-                filename,
-                timestamp
-            )
-        })
-        .map(|timestamp| ScreenCap::new(*timestamp, &mut info))
-        .enumerate()
-        .inspect(|_| pbar.inc(1));
-    for (idx, maybe_capture) in captures {
-        let capture = maybe_capture?;
-        imageops::replace(&mut img, &capture.thumbnail(), current_x, current_y);
+    if let Some(hero_tile) = &hero_tile {
+        imageops::replace(&mut img, hero_tile, current_x, current_y);
+        current_x += (cap_width + 2) as i64;
+    }
+    let captures = capture_frames(pbar, settings, path.as_ref(), &mut info, &times)?;
+    for (idx, capture) in captures.iter().enumerate() {
+        imageops::replace(&mut img, capture.image(), current_x, current_y);
+        if let Some(title) = chapter_at(&chapters, capture.timestamp()).and_then(|c| c.title()) {
+            if !chapter_labels.contains_key(title) {
+                let label_dims = Dimensions::new(cap_width, (cap_height / 8).max(8));
+                chapter_labels.insert(title.to_string(), render_text(&label_dims, title)?);
+            }
+            imageops::replace(&mut img, &chapter_labels[title], current_x, current_y);
+        }
+        if let Some(caption) = captions
+            .as_ref()
+            .and_then(|c| c.text_at(capture.timestamp()))
+        {
+            let caption = render_text(&caption_dims, &fit_caption(&caption, &caption_dims))?;
+            imageops::replace(&mut img, &caption, current_x, current_y + cap_height as i64);
+        }
         if ENV.save_individual_captures() {
-            save_individual_img(settings, &capture, &path, idx)?;
+            save_individual_img(settings, capture, &path, idx)?;
         }
         current_x += (cap_width + 2) as i64;
-        if idx != 0 && idx as u32 % settings.columns() == 0 {
-            current_y += (cap_height + 2) as i64;
+        let tile_idx = idx + first_tile;
+        if tile_idx != 0 && tile_idx as u32 % settings.columns() == 0 {
+            current_y += row_height as i64;
             current_x = 1;
         }
     }
+    if settings.waveform() {
+        img = add_waveform_strip(&mut info, img, &captures, cap_height)?;
+    }
     img.save_with_format(out_path.clone(), ImageFormat::Jpeg)?;
     finish_generation(pbar, path, out_path)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    /// A video whose frames are flat, with a brightness that depends on when they're shown. Fast
+    /// seeking lands on the keyframe at the start of every 10 seconds, and the other seek modes
+    /// land on the exact second. A frozen video shows the same frame throughout.
+    struct FakeVideo {
+        seek_mode: SeekMode,
+        frozen: bool,
+        /// The time and seek mode of every capture taken, in order.
+        captures: Vec<(Timestamp, SeekMode)>,
+    }
+
+    impl FakeVideo {
+        fn new(seek_mode: SeekMode, frozen: bool) -> Self {
+            Self {
+                seek_mode,
+                frozen,
+                captures: Vec::new(),
+            }
+        }
+    }
+
+    impl CaptureSource for FakeVideo {
+        fn seek_mode(&self) -> SeekMode {
+            self.seek_mode
+        }
+
+        fn path(&self) -> &Path {
+            Path::new("fake.mkv")
+        }
+
+        fn capture(&mut self, timestamp: Timestamp, seek_mode: SeekMode) -> Result<ScreenCap> {
+            self.captures.push((timestamp, seek_mode));
+            let second = match seek_mode {
+                _ if self.frozen => 0,
+                SeekMode::Fast => timestamp.seconds() as u32 / 10 * 10,
+                _ => timestamp.seconds() as u32,
+            };
+            let image = RgbImage::from_pixel(16, 16, Rgb([(second * 20).min(255) as u8; 3]));
+            Ok(ScreenCap::from_image(
+                timestamp,
+                Dimensions::new(16, 16),
+                Pixel::RGB24,
+                image,
+            ))
+        }
+    }
+
+    fn seconds(seconds: f64) -> Timestamp {
+        Timestamp::from_seconds(seconds)
+    }
+
+    #[test]
+    fn test_is_duplicate_of() {
+        let capture = |luma: u8| {
+            ScreenCap::from_image(
+                Timestamp::ZERO,
+                Dimensions::new(16, 16),
+                Pixel::RGB24,
+                RgbImage::from_pixel(16, 16, Rgb([luma; 3])),
+            )
+        };
+        assert!(capture(0).is_duplicate_of(&capture(4)));
+        // A fade to black next to a white flash isn't a duplicate, even though both are flat.
+        assert!(!capture(0).is_duplicate_of(&capture(255)));
+    }
+
+    #[test]
+    fn test_capture_chunk_retries_fast_duplicates_accurately() {
+        let mut video = FakeVideo::new(SeekMode::Fast, false);
+        let times = [seconds(1.0), seconds(3.0), seconds(5.0)];
+        let captures = capture_chunk(&ProgressBar::hidden(), &mut video, &times).unwrap();
+        // The capture at 3s lands on the same keyframe as the one at 1s, so it's taken again at the
+        // same time with accurate seeking. The one at 5s lands on that keyframe too, but it isn't a
+        // duplicate of the accurate capture before it.
+        assert_eq!(
+            video.captures,
+            vec![
+                (seconds(1.0), SeekMode::Fast),
+                (seconds(3.0), SeekMode::Fast),
+                (seconds(3.0), SeekMode::Accurate),
+                (seconds(5.0), SeekMode::Fast),
+            ]
+        );
+        assert_eq!(
+            captures.iter().map(|c| c.timestamp()).collect::<Vec<_>>(),
+            times
+        );
+        assert!(!captures[1].is_duplicate_of(&captures[0]));
+        assert!(!captures[2].is_duplicate_of(&captures[1]));
+    }
+
+    #[test]
+    fn test_avoid_duplicate_keeps_unavoidable_duplicate() {
+        let mut video = FakeVideo::new(SeekMode::Fast, true);
+        let previous = video.capture(seconds(10.0), SeekMode::Fast).unwrap();
+        let capture = video.capture(seconds(20.0), SeekMode::Fast).unwrap();
+        video.captures.clear();
+        let kept = avoid_duplicate(&mut video, &previous, capture, Some(seconds(30.0))).unwrap();
+        assert_eq!(
+            video.captures,
+            vec![
+                (seconds(20.0), SeekMode::Accurate),
+                (seconds(25.0), SeekMode::Accurate),
+            ]
+        );
+        assert_eq!(kept.timestamp(), seconds(20.0));
+
+        // Other seek modes go straight to nudging the time, and keep their seek mode.
+        let mut video = FakeVideo::new(SeekMode::Sequential, true);
+        let previous = video.capture(seconds(10.0), SeekMode::Sequential).unwrap();
+        let capture = video.capture(seconds(20.0), SeekMode::Sequential).unwrap();
+        video.captures.clear();
+        avoid_duplicate(&mut video, &previous, capture, None).unwrap();
+        assert_eq!(video.captures, vec![(seconds(25.0), SeekMode::Sequential)]);
+    }
+}
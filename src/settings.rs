@@ -1,12 +1,13 @@
-use crate::{Error, Result};
+use crate::{time::TrimAmount, Error, Result};
 use clap::Parser;
 use config::{
     builder::{ConfigBuilder as BaseConfigBuilder, DefaultState},
     Config, File as ConfigFile,
 };
 use directories::BaseDirs;
-use serde::{Deserialize, Serialize};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
 use std::{
+    fmt::{self, Display, Formatter},
     fs,
     path::{Path, PathBuf},
 };
@@ -55,9 +56,179 @@ pub struct Cli {
     #[arg(
         short,
         long,
-        help = "The percent of amount of the video to skip at the beginning of the file."
+        help = "How much of the video to skip at the beginning of the file, either as a percent \
+                (5 or 5%) or a duration (90s, 1:30)."
     )]
-    skip: Option<u32>,
+    skip: Option<String>,
+    #[arg(
+        long,
+        help = "How much of the video to skip at the end of the file, either as a percent (1 or \
+                1%) or a duration (8m, 8:00)."
+    )]
+    trim_end: Option<String>,
+    #[arg(
+        short = 'm',
+        long,
+        value_parser = ["segments", "interval", "keyframes", "chapters"],
+        help = "How to pick capture times: split the video into equal segments, capture every \
+                --interval seconds, capture every --keyframe-interval keyframes, or spread the \
+                captures across the video's chapters."
+    )]
+    capture_mode: Option<String>,
+    #[arg(
+        long,
+        help = "The number of seconds between captures when using the interval mode."
+    )]
+    interval: Option<f64>,
+    #[arg(
+        long,
+        help = "Capture every Nth keyframe when using the keyframes mode (1 captures every keyframe)."
+    )]
+    keyframe_interval: Option<u32>,
+    #[arg(
+        long = "at",
+        value_name = "TIMES",
+        value_delimiter = ',',
+        help = "Capture exactly these times (e.g. 90s, 1:30, 1200f for frame 1200) instead of \
+                generating them."
+    )]
+    timestamps: Vec<String>,
+    #[arg(long, help = "A file listing the exact times to capture.")]
+    timestamps_file: Option<String>,
+    #[arg(
+        long,
+        help = "Try to detect the opening titles and end credits (from chapter names or long \
+                stretches of text on black) and leave them out of the captures."
+    )]
+    detect_intro_credits: bool,
+    #[arg(
+        long,
+        value_parser = ["fast", "accurate", "keyframe", "sequential"],
+        help = "How to seek to each capture: fast lands on the next keyframe, accurate decodes \
+                forward from the previous keyframe to the exact time, keyframe only decodes the \
+                previous keyframe, and sequential decodes the whole video once without seeking \
+                (for badly indexed files). Fast and accurate switch to sequential if seeking \
+                doesn't work."
+    )]
+    seek_mode: Option<String>,
+    #[arg(
+        long,
+        help = "Have the decoder discard all non-key frames. Speeds up decoding, but can only be \
+                used with the keyframe seek mode."
+    )]
+    skip_nonkey_frames: bool,
+    #[arg(
+        long,
+        value_parser = ["videos", "captures"],
+        help = "Whether to process several videos at once, or one video at a time with its \
+                captures split between several decoders."
+    )]
+    parallelism: Option<String>,
+    #[arg(
+        long,
+        help = "How many decoders to split each video's captures between when parallelizing \
+                captures. Defaults to the number of threads."
+    )]
+    capture_workers: Option<usize>,
+    #[arg(
+        long,
+        help = "The number of threads each video's decoder uses. Defaults to letting ffmpeg decide."
+    )]
+    decoder_threads: Option<u32>,
+    #[arg(
+        long,
+        value_parser = ["auto", "frame", "slice"],
+        help = "What kind of threading the decoders use."
+    )]
+    decoder_thread_type: Option<String>,
+    #[arg(
+        long,
+        help = "Label each capture with the title of the chapter it's in."
+    )]
+    chapter_labels: bool,
+    #[arg(
+        long,
+        help = "Size the captures using the video's stored pixels instead of correcting for its \
+                sample aspect ratio, so anamorphic videos come out squished."
+    )]
+    storage_aspect: bool,
+    #[arg(
+        long,
+        value_parser = ["none", "clip", "linear", "gamma", "reinhard", "hable", "mobius"],
+        help = "The algorithm used to tone map HDR videos down to SDR, or none to leave them as \
+                they are."
+    )]
+    tonemap: Option<String>,
+    #[arg(
+        long,
+        value_parser = ["fast_bilinear", "bicubic", "lanczos", "area"],
+        help = "The algorithm used to scale frames down to the size of the captures. The slower \
+                ones avoid aliasing on detailed footage."
+    )]
+    scale_quality: Option<String>,
+    #[arg(
+        long,
+        value_name = "STREAM",
+        help = "Which video stream to capture: best (the default stream), largest (the highest \
+                resolution), a stream index, or a language code (e.g. eng)."
+    )]
+    video_stream: Option<String>,
+    #[arg(
+        long,
+        help = "Capture every video stream (e.g. multiple camera angles) at the same times, with \
+                each row of the sheet showing the streams side by side at one time. --rows sets \
+                the number of times. Subtitle captions, chapter labels and the cover art tile \
+                can't be shown on these sheets."
+    )]
+    all_video_streams: bool,
+    #[arg(
+        long,
+        help = "Save each video's embedded cover art next to its screencap file."
+    )]
+    extract_cover_art: bool,
+    #[arg(
+        long,
+        help = "Show each video's embedded cover art as the first tile of its screencap."
+    )]
+    cover_art_tile: bool,
+    #[arg(
+        long,
+        help = "Draw the audio's waveform in a strip beneath the captures, with a tick mark at \
+                each capture's time."
+    )]
+    waveform: bool,
+    #[arg(
+        long,
+        help = "Also make sheets for audio files, showing their cover art, tags and a \
+                visualization of the audio instead of captures."
+    )]
+    audio_files: bool,
+    #[arg(
+        long,
+        value_parser = ["spectrogram", "waveform"],
+        help = "How the audio of audio files is shown on their sheets."
+    )]
+    audio_visualization: Option<String>,
+    #[arg(
+        long,
+        help = "Draw the subtitles onto the captures. Picture-based subtitles (e.g. DVD and \
+                Blu-ray ones) are read in full first, which means reading through the whole file."
+    )]
+    burn_subtitles: bool,
+    #[arg(
+        long,
+        help = "Print the subtitle line shown at each capture beneath it. Subtitle files next to \
+                the video (e.g. video.srt or video.eng.ass) are used before the video's own \
+                subtitles, which are read in full first."
+    )]
+    subtitle_captions: bool,
+    #[arg(
+        long,
+        value_name = "LANGUAGE",
+        help = "The language code (e.g. eng) of the subtitles to use. Without it, the default \
+                subtitles are used."
+    )]
+    subtitle_language: Option<String>,
     #[arg(long)]
     config: Option<String>,
     #[arg(short, long)]
@@ -68,6 +239,180 @@ pub struct Cli {
 
 type ConfigBuilder = BaseConfigBuilder<DefaultState>;
 
+/// How to seek to the frame for each capture.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SeekMode {
+    /// Use the first frame decoded after seeking, which is usually the next keyframe.
+    Fast,
+    /// Seek to the keyframe before the capture time and decode forward to the exact frame.
+    Accurate,
+    /// Seek to the keyframe before the capture time and only decode that frame.
+    Keyframe,
+    /// Don't seek at all; decode the whole video once and capture frames as they pass by. This is
+    /// also what fast and accurate seeking fall back to when seeking fails, produces no frame, or
+    /// accurate seeking produces repeated frames.
+    Sequential,
+}
+
+/// What to process in parallel.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Parallelism {
+    /// Process several videos at once, capturing each video's frames one at a time.
+    Videos,
+    /// Process one video at a time, splitting its captures between several decoders.
+    Captures,
+}
+
+/// What kind of threading a decoder uses.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DecoderThreadType {
+    /// Let the decoder use whichever kinds of threading it supports.
+    Auto,
+    /// Decode multiple frames at once.
+    Frame,
+    /// Decode multiple parts of a single frame at once.
+    Slice,
+}
+
+impl DecoderThreadType {
+    /// The value of ffmpeg's `thread_type` option for this type.
+    pub fn ffmpeg_name(&self) -> &'static str {
+        match self {
+            Self::Auto => "frame+slice",
+            Self::Frame => "frame",
+            Self::Slice => "slice",
+        }
+    }
+}
+
+/// How HDR videos are tone mapped down to SDR. Apart from `None`, these are the algorithms of
+/// ffmpeg's `tonemap` filter.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ToneMapAlgorithm {
+    /// Don't tone map at all, which leaves HDR captures looking washed out.
+    None,
+    /// Clip any out of range values.
+    Clip,
+    /// Stretch the whole range linearly.
+    Linear,
+    /// Stretch the range with a gamma curve.
+    Gamma,
+    /// A simple curve that preserves the overall brightness.
+    Reinhard,
+    /// A filmic curve that preserves detail in both the highlights and the shadows.
+    Hable,
+    /// A curve that leaves in range values alone and only compresses the highlights.
+    Mobius,
+}
+
+impl ToneMapAlgorithm {
+    /// The value of the `tonemap` filter's `tonemap` option for this algorithm, if there is one.
+    pub fn ffmpeg_name(&self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Clip => Some("clip"),
+            Self::Linear => Some("linear"),
+            Self::Gamma => Some("gamma"),
+            Self::Reinhard => Some("reinhard"),
+            Self::Hable => Some("hable"),
+            Self::Mobius => Some("mobius"),
+        }
+    }
+}
+
+/// The algorithm used to scale frames to the size of the captures.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScaleQuality {
+    /// The fastest, but prone to aliasing.
+    FastBilinear,
+    /// A good balance between speed and sharpness.
+    Bicubic,
+    /// The sharpest, and the slowest.
+    Lanczos,
+    /// Averages the pixels being combined, which works well for large reductions.
+    Area,
+}
+
+impl ScaleQuality {
+    /// The value of the scale filter's `flags` option for this algorithm.
+    pub fn ffmpeg_name(&self) -> &'static str {
+        match self {
+            Self::FastBilinear => "fast_bilinear",
+            Self::Bicubic => "bicubic",
+            Self::Lanczos => "lanczos",
+            Self::Area => "area",
+        }
+    }
+}
+
+/// How the audio of an audio file is shown on its sheet.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioVisualization {
+    /// A picture of the frequencies over the whole file.
+    Spectrogram,
+    /// The peak level over the whole file.
+    Waveform,
+}
+
+/// How to pick which of a file's video streams to capture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamSelector {
+    /// The stream ffmpeg considers the best, which is usually the default one.
+    Best,
+    /// The stream with the highest resolution.
+    Largest,
+    /// The stream with this index in the file.
+    Index(usize),
+    /// The best stream with this language code.
+    Language(String),
+}
+
+impl StreamSelector {
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+        match value.to_lowercase().as_str() {
+            "" | "best" => Self::Best,
+            "largest" => Self::Largest,
+            lower => match value.parse() {
+                Ok(index) => Self::Index(index),
+                Err(_) => Self::Language(lower.to_string()),
+            },
+        }
+    }
+}
+
+impl Display for StreamSelector {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Best => f.write_str("best"),
+            Self::Largest => f.write_str("largest"),
+            Self::Index(idx) => write!(f, "index {}", idx),
+            Self::Language(language) => write!(f, "language {}", language),
+        }
+    }
+}
+
+/// How the times to capture frames at are chosen.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureMode {
+    /// Split the video into `columns * rows` equal segments.
+    Segments,
+    /// Capture a frame every `interval` seconds, adding rows as needed.
+    Interval,
+    /// Capture every `keyframe_interval`th keyframe, adding rows as needed.
+    Keyframes,
+    /// Spread `columns * rows` captures across the video's chapters, with at least one capture per
+    /// chapter.
+    Chapters,
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Settings {
     keep_files: bool,
@@ -81,13 +426,58 @@ pub struct Settings {
     rows: u32,
     threads: Option<u8>,
     save_failures_to_ignore: bool,
-    skip: usize,
+    skip: TrimAmount,
+    trim_end: TrimAmount,
+    detect_intro_credits: bool,
+    capture_mode: CaptureMode,
+    #[serde(deserialize_with = "positive_seconds")]
+    interval: f64,
+    keyframe_interval: u32,
+    chapter_labels: bool,
+    seek_mode: SeekMode,
+    skip_nonkey_frames: bool,
+    parallelism: Parallelism,
+    capture_workers: Option<usize>,
+    decoder_threads: Option<u32>,
+    decoder_thread_type: DecoderThreadType,
+    storage_aspect: bool,
+    tonemap: ToneMapAlgorithm,
+    scale_quality: ScaleQuality,
+    video_stream: String,
+    all_video_streams: bool,
+    extract_cover_art: bool,
+    cover_art_tile: bool,
+    waveform: bool,
+    audio_files: bool,
+    audio_visualization: AudioVisualization,
+    burn_subtitles: bool,
+    subtitle_captions: bool,
+    subtitle_language: Option<String>,
+    #[serde(default)]
+    timestamps: Vec<String>,
+    timestamps_file: Option<PathBuf>,
     out_dir: PathBuf,
     allow_links: bool,
     #[serde(skip_serializing)]
     input: Vec<PathBuf>,
 }
 
+/// Deserializes a number of seconds that has to be positive, like the time between captures (which
+/// would otherwise produce a capture for every microsecond of the video).
+fn positive_seconds<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<f64, D::Error> {
+    let seconds = f64::deserialize(deserializer)?;
+    if seconds.is_finite() && seconds > 0.0 {
+        Ok(seconds)
+    } else {
+        Err(D::Error::custom(format!(
+            "expected a positive number of seconds, got {}",
+            seconds
+        )))
+    }
+}
+
 impl Settings {
     pub fn load() -> Result<Self> {
         let cli = Cli::parse();
@@ -99,7 +489,31 @@ impl Settings {
             conf_builder = Self::load_from_file(".mk-screens.yaml", conf_builder)?;
         }
         conf_builder = Self::merge_cli_args(cli, conf_builder)?;
-        Ok(conf_builder.build()?.try_deserialize()?)
+        let settings: Self = conf_builder.build()?.try_deserialize()?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Checks for settings that can't be used together.
+    fn validate(&self) -> Result<()> {
+        if self.skip_nonkey_frames && self.seek_mode != SeekMode::Keyframe {
+            return Err(Error::Other {
+                message: format!(
+                    "skip_nonkey_frames can only be used with the keyframe seek mode, since the \
+                     {:?} seek mode needs to decode the frames between keyframes.",
+                    self.seek_mode
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Loads the default settings, ignoring the command line and any config files.
+    pub fn from_defaults() -> Result<Self> {
+        Ok(Self::get_default_config()?
+            .set_default("input", Vec::<String>::new())?
+            .build()?
+            .try_deserialize()?)
     }
 
     fn merge_cli_args(cli: Cli, mut conf_builder: ConfigBuilder) -> Result<ConfigBuilder> {
@@ -127,12 +541,63 @@ impl Settings {
         if cli.verbose {
             conf_builder = conf_builder.set_override("verbose", true)?;
         }
+        if cli.detect_intro_credits {
+            conf_builder = conf_builder.set_override("detect_intro_credits", true)?;
+        }
+        if cli.skip_nonkey_frames {
+            conf_builder = conf_builder.set_override("skip_nonkey_frames", true)?;
+        }
+        if cli.chapter_labels {
+            conf_builder = conf_builder.set_override("chapter_labels", true)?;
+        }
+        if cli.storage_aspect {
+            conf_builder = conf_builder.set_override("storage_aspect", true)?;
+        }
+        if cli.all_video_streams {
+            conf_builder = conf_builder.set_override("all_video_streams", true)?;
+        }
+        if cli.extract_cover_art {
+            conf_builder = conf_builder.set_override("extract_cover_art", true)?;
+        }
+        if cli.cover_art_tile {
+            conf_builder = conf_builder.set_override("cover_art_tile", true)?;
+        }
+        if cli.waveform {
+            conf_builder = conf_builder.set_override("waveform", true)?;
+        }
+        if cli.audio_files {
+            conf_builder = conf_builder.set_override("audio_files", true)?;
+        }
+        if cli.burn_subtitles {
+            conf_builder = conf_builder.set_override("burn_subtitles", true)?;
+        }
+        if cli.subtitle_captions {
+            conf_builder = conf_builder.set_override("subtitle_captions", true)?;
+        }
+        if !cli.timestamps.is_empty() {
+            conf_builder = conf_builder.set_override("timestamps", cli.timestamps)?;
+        }
         Ok(conf_builder
             .set_override_option("width", cli.width)?
             .set_override_option("columns", cli.columns)?
             .set_override_option("rows", cli.rows)?
             .set_override_option("threads", cli.threads)?
             .set_override_option("skip", cli.skip)?
+            .set_override_option("trim_end", cli.trim_end)?
+            .set_override_option("capture_mode", cli.capture_mode)?
+            .set_override_option("interval", cli.interval)?
+            .set_override_option("keyframe_interval", cli.keyframe_interval)?
+            .set_override_option("timestamps_file", cli.timestamps_file)?
+            .set_override_option("seek_mode", cli.seek_mode)?
+            .set_override_option("parallelism", cli.parallelism)?
+            .set_override_option("capture_workers", cli.capture_workers.map(|w| w as u64))?
+            .set_override_option("decoder_threads", cli.decoder_threads)?
+            .set_override_option("decoder_thread_type", cli.decoder_thread_type)?
+            .set_override_option("tonemap", cli.tonemap)?
+            .set_override_option("scale_quality", cli.scale_quality)?
+            .set_override_option("video_stream", cli.video_stream)?
+            .set_override_option("audio_visualization", cli.audio_visualization)?
+            .set_override_option("subtitle_language", cli.subtitle_language)?
             .set_override_option("out_dir", cli.out_dir)?
             .set_override("input", cli.input)?)
     }
@@ -193,6 +658,28 @@ impl Settings {
             .set_default("columns", 12)?
             .set_default("rows", 12)?
             .set_default("skip", 5)?
+            .set_default("trim_end", "1%")?
+            .set_default("detect_intro_credits", false)?
+            .set_default("capture_mode", "segments")?
+            .set_default("interval", 10.0)?
+            .set_default("keyframe_interval", 1)?
+            .set_default("chapter_labels", false)?
+            .set_default("seek_mode", "fast")?
+            .set_default("skip_nonkey_frames", false)?
+            .set_default("parallelism", "videos")?
+            .set_default("decoder_thread_type", "auto")?
+            .set_default("storage_aspect", false)?
+            .set_default("tonemap", "hable")?
+            .set_default("scale_quality", "fast_bilinear")?
+            .set_default("video_stream", "best")?
+            .set_default("all_video_streams", false)?
+            .set_default("extract_cover_art", false)?
+            .set_default("cover_art_tile", false)?
+            .set_default("waveform", false)?
+            .set_default("audio_files", false)?
+            .set_default("audio_visualization", "spectrogram")?
+            .set_default("burn_subtitles", false)?
+            .set_default("subtitle_captions", false)?
             .set_default("out_dir", "screens")?)
     }
 
@@ -244,8 +731,156 @@ impl Settings {
         self.threads
     }
 
-    pub fn skip(&self) -> f64 {
-        (self.skip as f64) / 100.0
+    /// How much of the video to skip at the beginning of the file.
+    pub fn skip(&self) -> TrimAmount {
+        self.skip
+    }
+
+    /// How much of the video to skip at the end of the file.
+    pub fn trim_end(&self) -> TrimAmount {
+        self.trim_end
+    }
+
+    /// Whether to try to leave the opening titles and end credits out of the captures.
+    pub fn detect_intro_credits(&self) -> bool {
+        self.detect_intro_credits
+    }
+
+    pub fn capture_mode(&self) -> CaptureMode {
+        self.capture_mode
+    }
+
+    /// The number of seconds between captures when using [`CaptureMode::Interval`].
+    pub fn interval(&self) -> f64 {
+        self.interval
+    }
+
+    /// Every how many keyframes to capture when using [`CaptureMode::Keyframes`].
+    pub fn keyframe_interval(&self) -> u32 {
+        self.keyframe_interval.max(1)
+    }
+
+    pub fn seek_mode(&self) -> SeekMode {
+        self.seek_mode
+    }
+
+    /// Whether the decoder should discard all non-key frames.
+    pub fn skip_nonkey_frames(&self) -> bool {
+        self.skip_nonkey_frames
+    }
+
+    pub fn parallelism(&self) -> Parallelism {
+        self.parallelism
+    }
+
+    /// How many decoders to split each video's captures between. This is always 1 unless
+    /// parallelizing captures.
+    pub fn capture_workers(&self) -> usize {
+        match self.parallelism {
+            Parallelism::Videos => 1,
+            Parallelism::Captures => self
+                .capture_workers
+                .unwrap_or_else(rayon::current_num_threads)
+                .max(1),
+        }
+    }
+
+    /// The number of threads each decoder uses, or `None` to let ffmpeg decide.
+    pub fn decoder_threads(&self) -> Option<u32> {
+        self.decoder_threads
+    }
+
+    pub fn decoder_thread_type(&self) -> DecoderThreadType {
+        self.decoder_thread_type
+    }
+
+    /// Whether to label each capture with the title of the chapter it's in.
+    pub fn chapter_labels(&self) -> bool {
+        self.chapter_labels
+    }
+
+    /// Whether to size captures by the video's storage dimensions, ignoring its sample aspect ratio.
+    pub fn storage_aspect(&self) -> bool {
+        self.storage_aspect
+    }
+
+    /// How HDR videos are tone mapped down to SDR.
+    pub fn tonemap(&self) -> ToneMapAlgorithm {
+        self.tonemap
+    }
+
+    /// The algorithm used to scale frames to the size of the captures.
+    pub fn scale_quality(&self) -> ScaleQuality {
+        self.scale_quality
+    }
+
+    /// Which of a file's video streams to capture.
+    pub fn video_stream(&self) -> StreamSelector {
+        StreamSelector::parse(&self.video_stream)
+    }
+
+    /// Whether to capture every video stream side by side.
+    pub fn all_video_streams(&self) -> bool {
+        self.all_video_streams
+    }
+
+    /// Whether to save each video's embedded cover art to its own file.
+    pub fn extract_cover_art(&self) -> bool {
+        self.extract_cover_art
+    }
+
+    /// Whether to show each video's embedded cover art as the first tile.
+    pub fn cover_art_tile(&self) -> bool {
+        self.cover_art_tile
+    }
+
+    /// Whether to draw a strip with the audio's waveform beneath the captures.
+    pub fn waveform(&self) -> bool {
+        self.waveform
+    }
+
+    /// Whether to make sheets for audio files as well as videos.
+    pub fn audio_files(&self) -> bool {
+        self.audio_files
+    }
+
+    /// How the audio of audio files is shown on their sheets.
+    pub fn audio_visualization(&self) -> AudioVisualization {
+        self.audio_visualization
+    }
+
+    /// Whether to draw the subtitles onto the captures.
+    pub fn burn_subtitles(&self) -> bool {
+        self.burn_subtitles
+    }
+
+    /// Whether to print the subtitles beneath each capture.
+    pub fn subtitle_captions(&self) -> bool {
+        self.subtitle_captions
+    }
+
+    /// The language of the subtitles to use, or `None` to use the default subtitles.
+    pub fn subtitle_language(&self) -> Option<&str> {
+        self.subtitle_language.as_deref()
+    }
+
+    /// A copy of these settings with a different grid layout.
+    pub fn with_layout(&self, columns: u32, rows: u32) -> Self {
+        Self {
+            columns,
+            rows,
+            ..self.clone()
+        }
+    }
+
+    /// The exact times to capture, as provided by the user.
+    pub fn timestamps(&self) -> &[String] {
+        &self.timestamps
+    }
+
+    /// A file listing the exact times to capture.
+    pub fn timestamps_file(&self) -> Option<&Path> {
+        self.timestamps_file.as_deref()
     }
 
     pub fn fix_times(&self) -> bool {
@@ -260,3 +895,36 @@ impl Settings {
         &self.input
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Interval {
+        #[serde(deserialize_with = "positive_seconds")]
+        interval: f64,
+    }
+
+    #[test]
+    fn test_positive_seconds() {
+        let parse = |yaml: &str| serde_yaml::from_str::<Interval>(yaml).map(|i| i.interval);
+        assert_eq!(parse("interval: 2.5").unwrap(), 2.5);
+        assert_eq!(parse("interval: 10").unwrap(), 10.0);
+        assert!(parse("interval: 0").is_err());
+        assert!(parse("interval: -5").is_err());
+        assert!(parse("interval: .nan").is_err());
+        assert!(parse("interval: .inf").is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut settings = Settings::from_defaults().unwrap();
+        assert!(settings.validate().is_ok());
+        settings.skip_nonkey_frames = true;
+        settings.seek_mode = SeekMode::Accurate;
+        assert!(settings.validate().is_err());
+        settings.seek_mode = SeekMode::Keyframe;
+        assert!(settings.validate().is_ok());
+    }
+}
@@ -0,0 +1,668 @@
+//! Items for finding a video's subtitles and drawing them onto its captures.
+use std::{fs, path::Path};
+
+use ffmpeg::{
+    codec::{
+        context::Context as CodecContext, decoder::Subtitle as SubtitleDecoder, subtitle::Rect,
+    },
+    filter,
+    format::{
+        context::Input,
+        stream::{Disposition, Stream},
+    },
+    util::media::Type as MediaType,
+    Subtitle,
+};
+use image::{imageops, imageops::FilterType, Rgb, RgbImage, RgbaImage};
+
+use crate::{
+    ffmpeg_ext::{
+        FrameSeekable as _, HasDimensions as _, IsBitmapSubtitle as _, SeekFlags, ToRgbaImage as _,
+    },
+    files::subtitle_sidecar_paths,
+    text::escape_filter_value,
+    time::{parse_duration, Clock, Timestamp},
+    util::Dimensions,
+    video::stream_language,
+    Result,
+};
+
+/// Seeks back to the start of a file so that all of a stream can be read, wherever the input was
+/// left by whatever read it last. Seeking by byte is tried first since it works for files without an
+/// index, then by time for formats (like subtitle files) that can't seek by byte.
+fn seek_to_start(input: &mut Input) {
+    if input.seek_to_frame(-1, 0, SeekFlags::BYTE).is_ok() {
+        return;
+    }
+    if input.seek(0, ..).is_err() {
+        log::debug!("Could not seek to the start of the file to read its subtitles.");
+    }
+}
+
+/// The subtitle streams in the provided language, or all of them if no language is given.
+fn subtitle_streams<'a>(
+    input: &'a Input,
+    language: Option<&str>,
+) -> impl Iterator<Item = Stream<'a>> + 'a {
+    let language = language.map(str::to_lowercase);
+    input
+        .streams()
+        .filter(|s| s.parameters().medium() == MediaType::Subtitle)
+        .filter(move |s| language.is_none() || stream_language(s) == language)
+}
+
+/// Finds the subtitle stream to use: the default one (or else the first one) out of the streams in
+/// the provided language, or out of all of them if no language is given.
+pub fn find_subtitle_stream<'a>(input: &'a Input, language: Option<&str>) -> Option<Stream<'a>> {
+    subtitle_streams(input, language)
+        .min_by_key(|s| !s.disposition().contains(Disposition::DEFAULT))
+}
+
+/// Like [`find_subtitle_stream`], but leaving out bitmap subtitles, which have no text to read.
+pub fn find_text_subtitle_stream<'a>(
+    input: &'a Input,
+    language: Option<&str>,
+) -> Option<Stream<'a>> {
+    subtitle_streams(input, language)
+        .filter(|s| !s.parameters().id().is_bitmap_subtitle())
+        .min_by_key(|s| !s.disposition().contains(Disposition::DEFAULT))
+}
+
+fn open_subtitle_decoder(stream: &Stream) -> Result<SubtitleDecoder> {
+    Ok(CodecContext::from_parameters(stream.parameters())?
+        .decoder()
+        .subtitle()?)
+}
+
+/// Removes the formatting from a line of subtitles: ASS override tags (`{\i1}`), HTML-style tags
+/// (`<i>`) and ASS line breaks, leaving the text on a single line.
+pub fn strip_markup(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut closing = None;
+    for chr in text.chars() {
+        match (closing, chr) {
+            (None, '{') => closing = Some('}'),
+            (None, '<') => closing = Some('>'),
+            (None, chr) => plain.push(chr),
+            (Some(close), chr) if chr == close => closing = None,
+            _ => (),
+        }
+    }
+    plain
+        .replace("\\N", " ")
+        .replace("\\n", " ")
+        .replace("\\h", " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parses a subtitle file's timestamp, which is `H:MM:SS,mmm` in SRT files and `H:MM:SS.cc` in ASS
+/// files.
+fn parse_subtitle_time(value: &str) -> Option<Timestamp> {
+    parse_duration(&value.trim().replace(',', ".")).map(Timestamp::from_seconds)
+}
+
+/// Parses the contents of an SRT file. Malformed cues are skipped.
+pub fn parse_srt(content: &str) -> Vec<TextCue> {
+    let mut cues = Vec::new();
+    let mut lines = content.trim_start_matches('\u{feff}').lines();
+    while let Some(line) = lines.next() {
+        let Some((start, end)) = line.split_once("-->") else {
+            continue;
+        };
+        // The end time can be followed by positioning information.
+        let end = end.split_whitespace().next().and_then(parse_subtitle_time);
+        let text: Vec<&str> = lines
+            .by_ref()
+            .take_while(|line| !line.trim().is_empty())
+            .collect();
+        if let (Some(start), Some(end)) = (parse_subtitle_time(start), end) {
+            cues.push(TextCue::new(start, Some(end), &text.join(" ")));
+        }
+    }
+    cues
+}
+
+/// The fields of an ASS file's events when its `[Events]` section doesn't say.
+const DEFAULT_ASS_FORMAT: [&str; 10] = [
+    "layer", "start", "end", "style", "name", "marginl", "marginr", "marginv", "effect", "text",
+];
+
+/// Parses the dialogue of an ASS (or SSA) file. Malformed events are skipped.
+pub fn parse_ass(content: &str) -> Vec<TextCue> {
+    let mut cues = Vec::new();
+    let mut in_events = false;
+    let mut format: Vec<String> = DEFAULT_ASS_FORMAT.iter().map(|f| f.to_string()).collect();
+    for line in content.trim_start_matches('\u{feff}').lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_events = line.eq_ignore_ascii_case("[events]");
+        } else if !in_events {
+            continue;
+        } else if let Some(fields) = line.strip_prefix("Format:") {
+            format = fields.split(',').map(|f| f.trim().to_lowercase()).collect();
+        } else if let Some(fields) = line.strip_prefix("Dialogue:") {
+            // The text is always the last field, and can contain commas itself.
+            let fields: Vec<&str> = fields.splitn(format.len(), ',').collect();
+            let field = |name: &str| {
+                format
+                    .iter()
+                    .position(|f| f == name)
+                    .and_then(|idx| fields.get(idx))
+            };
+            let start = field("start").and_then(|start| parse_subtitle_time(start));
+            let end = field("end").and_then(|end| parse_subtitle_time(end));
+            if let (Some(start), Some(end), Some(text)) = (start, end, field("text")) {
+                cues.push(TextCue::new(start, Some(end), text));
+            }
+        }
+    }
+    cues
+}
+
+/// The text of an ASS event as ffmpeg's decoders produce it, which is a `Dialogue` line without its
+/// times: `ReadOrder,Layer,Style,Name,MarginL,MarginR,MarginV,Effect,Text`.
+fn ass_event_text(event: &str) -> &str {
+    event.splitn(9, ',').nth(8).unwrap_or(event)
+}
+
+/// A line of text subtitles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextCue {
+    start: Timestamp,
+    /// When the line stops being shown, or `None` if it's shown until the next one.
+    end: Option<Timestamp>,
+    text: String,
+}
+
+impl TextCue {
+    /// Creates a cue, removing the formatting from its text.
+    pub fn new(start: Timestamp, end: Option<Timestamp>, text: &str) -> Self {
+        Self {
+            start,
+            end,
+            text: strip_markup(text),
+        }
+    }
+
+    fn is_shown_at(&self, timestamp: Timestamp) -> bool {
+        self.start <= timestamp && self.end.is_none_or(|end| timestamp < end)
+    }
+}
+
+/// The lines of a set of text subtitles, from either a subtitle stream or a subtitle file next to
+/// the video.
+#[derive(Debug, Clone, Default)]
+pub struct TextSubtitles {
+    cues: Vec<TextCue>,
+}
+
+impl TextSubtitles {
+    /// Creates subtitles from cues in any order. Cues without an end are shown until the next one
+    /// starts.
+    pub fn new(mut cues: Vec<TextCue>) -> Self {
+        cues.retain(|c| !c.text.is_empty());
+        cues.sort_by_key(|c| c.start);
+        let starts: Vec<Timestamp> = cues.iter().map(|c| c.start).collect();
+        for (cue, next) in cues.iter_mut().zip(starts.into_iter().skip(1)) {
+            cue.end = cue.end.or(Some(next));
+        }
+        Self { cues }
+    }
+
+    /// Reads a subtitle file. ASS and SSA files are recognized by their extension, and everything
+    /// else is read as SRT.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+        let is_ass = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("ass") || ext.eq_ignore_ascii_case("ssa"));
+        Ok(Self::new(if is_ass {
+            parse_ass(&content)
+        } else {
+            parse_srt(&content)
+        }))
+    }
+
+    /// Reads all of the lines of a text subtitle stream, which means reading through the whole file.
+    /// `clock` is the video stream's clock, so that the subtitles are timed like the captures. The
+    /// input is seeked to the start first, and back to it afterwards.
+    pub fn read(input: &mut Input, stream_idx: usize, clock: &Clock) -> Result<Self> {
+        seek_to_start(input);
+        let stream = input.stream(stream_idx).unwrap();
+        let clock = clock.with_time_base(stream.time_base());
+        let mut decoder = open_subtitle_decoder(&stream)?;
+        let mut cues = Vec::new();
+        let mut subtitle = Subtitle::new();
+        for (s, packet) in input.packets() {
+            if s.index() != stream_idx {
+                continue;
+            }
+            let Some(pts) = packet.pts() else {
+                continue;
+            };
+            if !matches!(decoder.decode(&packet, &mut subtitle), Ok(true)) {
+                continue;
+            }
+            let text = subtitle
+                .rects()
+                .filter_map(|rect| match rect {
+                    Rect::Text(text) => Some(text.get().to_string()),
+                    Rect::Ass(ass) => Some(ass_event_text(ass.get()).to_string()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            let end = (packet.duration() > 0).then(|| clock.from_clock(pts + packet.duration()));
+            cues.push(TextCue::new(clock.from_clock(pts), end, &text));
+        }
+        seek_to_start(input);
+        log::debug!("Read {} subtitle lines", cues.len());
+        Ok(Self::new(cues))
+    }
+
+    /// Finds the subtitles to caption a video's captures with. A subtitle file next to the video
+    /// (see [`subtitle_sidecar_paths`]) is used if there is one, since it was put there on purpose,
+    /// and otherwise the video's text subtitle stream is used.
+    pub fn open(
+        input: &mut Input,
+        path: &Path,
+        language: Option<&str>,
+        clock: &Clock,
+    ) -> Result<Option<Self>> {
+        if let Some(sidecar) = subtitle_sidecar_paths(path, language)
+            .into_iter()
+            .find(|p| p.is_file())
+        {
+            log::debug!("Using subtitles from {}", sidecar.display());
+            return Self::from_file(sidecar).map(Some);
+        }
+        match find_text_subtitle_stream(input, language).map(|s| s.index()) {
+            Some(stream_idx) => Self::read(input, stream_idx, clock).map(Some),
+            None => {
+                log::warn!(
+                    "{} has no text subtitles{} to caption the captures with",
+                    path.display(),
+                    language.map(|l| format!(" in {}", l)).unwrap_or_default()
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    /// The text shown at a timestamp, if there is any. Overlapping lines are joined together.
+    pub fn text_at(&self, timestamp: Timestamp) -> Option<String> {
+        let lines: Vec<&str> = self
+            .cues
+            .iter()
+            .filter(|c| c.is_shown_at(timestamp))
+            .map(|c| c.text.as_str())
+            .collect();
+        (!lines.is_empty()).then(|| lines.join(" "))
+    }
+}
+
+/// The arguments for ffmpeg's `subtitles` filter to draw a text subtitle stream of a file. The
+/// filter picks the stream by its position among the file's subtitle streams, and needs the size of
+/// the original video to size the text correctly once the frames have been scaled.
+pub fn subtitles_filter_args(path: &Path, position: usize, video_dims: &Dimensions) -> String {
+    [
+        format!("filename={}", escape_filter_value(&path.to_string_lossy())),
+        format!("si={}", position),
+        format!("original_size={}", video_dims),
+    ]
+    .join(":")
+}
+
+/// Draws an image with transparency onto an opaque one, with its top left corner at `(x, y)`.
+/// Anything that falls outside of the opaque image is left out.
+fn blend(img: &mut RgbImage, overlay: &RgbaImage, x: i64, y: i64) {
+    for (ox, oy, pixel) in overlay.enumerate_pixels() {
+        let (px, py) = (x + ox as i64, y + oy as i64);
+        if px < 0 || py < 0 || px >= img.width() as i64 || py >= img.height() as i64 {
+            continue;
+        }
+        let alpha = pixel[3] as u32;
+        let under = img.get_pixel_mut(px as u32, py as u32);
+        *under = Rgb(std::array::from_fn(|c| {
+            ((pixel[c] as u32 * alpha + under[c] as u32 * (255 - alpha)) / 255) as u8
+        }));
+    }
+}
+
+/// A picture from a bitmap subtitle stream, positioned on the subtitles' canvas.
+#[derive(Debug, Clone)]
+struct BitmapCue {
+    start: Timestamp,
+    /// When the picture stops being shown, or `None` if it's shown until the next one.
+    end: Option<Timestamp>,
+    x: u32,
+    y: u32,
+    image: RgbaImage,
+}
+
+impl BitmapCue {
+    fn is_shown_at(&self, timestamp: Timestamp) -> bool {
+        self.start <= timestamp && self.end.is_none_or(|end| timestamp < end)
+    }
+}
+
+/// The pictures of a bitmap subtitle stream (like DVD and Blu-ray subtitles). ffmpeg's `subtitles`
+/// filter only handles text, so these are all read up front and drawn onto the captures instead.
+#[derive(Debug, Clone)]
+pub struct BitmapSubtitles {
+    /// The size of the picture the subtitles are positioned on.
+    canvas: Dimensions,
+    cues: Vec<BitmapCue>,
+}
+
+impl BitmapSubtitles {
+    /// Reads all of the pictures of a subtitle stream, which means reading through the whole file.
+    /// `clock` is the video stream's clock, so that the subtitles are timed like the captures. The
+    /// input is seeked to the start first, and back to it afterwards.
+    pub fn read(
+        input: &mut Input,
+        stream_idx: usize,
+        clock: &Clock,
+        video_dims: &Dimensions,
+    ) -> Result<Self> {
+        seek_to_start(input);
+        let stream = input.stream(stream_idx).unwrap();
+        let clock = clock.with_time_base(stream.time_base());
+        let canvas = match stream.parameters().dimensions() {
+            dims if dims.width() > 0 && dims.height() > 0 => dims,
+            _ => video_dims.clone(),
+        };
+        let mut decoder = open_subtitle_decoder(&stream)?;
+        let mut cues: Vec<BitmapCue> = Vec::new();
+        let mut subtitle = Subtitle::new();
+        for (s, packet) in input.packets() {
+            if s.index() != stream_idx {
+                continue;
+            }
+            let Some(pts) = packet.pts() else {
+                continue;
+            };
+            if !matches!(decoder.decode(&packet, &mut subtitle), Ok(true)) {
+                continue;
+            }
+            let shown_at = clock.from_clock(pts).av_time();
+            let start = Timestamp::new(shown_at + subtitle.start() as i64 * 1000);
+            let end = (subtitle.end() > subtitle.start() && subtitle.end() != u32::MAX)
+                .then(|| Timestamp::new(shown_at + subtitle.end() as i64 * 1000));
+            // Each subtitle replaces the ones before it (an empty one just clears them).
+            for cue in cues.iter_mut().rev().take_while(|c| c.start < start) {
+                if cue.end.is_none_or(|end| end > start) {
+                    cue.end = Some(start);
+                }
+            }
+            for rect in subtitle.rects() {
+                if let Rect::Bitmap(bitmap) = rect {
+                    if let Some(image) = bitmap.to_rgba_image() {
+                        cues.push(BitmapCue {
+                            start,
+                            end,
+                            x: bitmap.x() as u32,
+                            y: bitmap.y() as u32,
+                            image,
+                        });
+                    }
+                }
+            }
+        }
+        seek_to_start(input);
+        log::debug!("Read {} subtitle pictures", cues.len());
+        Ok(Self { canvas, cues })
+    }
+
+    /// Draws the subtitles shown at a timestamp onto a capture, scaling them from the subtitles'
+    /// canvas to the size of the capture.
+    pub fn draw(&self, img: &mut RgbImage, timestamp: Timestamp) {
+        let scale_x = img.width() as f64 / self.canvas.width() as f64;
+        let scale_y = img.height() as f64 / self.canvas.height() as f64;
+        for cue in self.cues.iter().filter(|c| c.is_shown_at(timestamp)) {
+            let picture = imageops::resize(
+                &cue.image,
+                ((cue.image.width() as f64 * scale_x).round() as u32).max(1),
+                ((cue.image.height() as f64 * scale_y).round() as u32).max(1),
+                FilterType::Triangle,
+            );
+            blend(
+                img,
+                &picture,
+                (cue.x as f64 * scale_x).round() as i64,
+                (cue.y as f64 * scale_y).round() as i64,
+            );
+        }
+    }
+}
+
+/// How a video's subtitles are burned into its captures.
+#[derive(Debug, Clone)]
+pub enum SubtitleBurnIn {
+    /// Text subtitles, drawn by ffmpeg's `subtitles` filter with these arguments.
+    Filter(String),
+    /// Bitmap subtitles, drawn onto the captures once they've been converted to images.
+    Bitmaps(BitmapSubtitles),
+}
+
+impl SubtitleBurnIn {
+    /// Finds the subtitles to burn into the captures of a video stream, or `None` if the video has
+    /// no subtitles (in the provided language) or they're text subtitles and ffmpeg can't draw them.
+    pub fn open(
+        input: &mut Input,
+        path: &Path,
+        language: Option<&str>,
+        video_stream_idx: usize,
+    ) -> Result<Option<Self>> {
+        let Some(stream) = find_subtitle_stream(input, language) else {
+            log::warn!(
+                "{} has no subtitles{} to burn in",
+                path.display(),
+                language.map(|l| format!(" in {}", l)).unwrap_or_default()
+            );
+            return Ok(None);
+        };
+        let subtitle_idx = stream.index();
+        let is_bitmap = stream.parameters().id().is_bitmap_subtitle();
+        let video = input.stream(video_stream_idx).unwrap();
+        let clock = Clock::new(video.time_base(), video.start_time());
+        let video_dims = video.parameters().dimensions();
+        if is_bitmap {
+            let subtitles = BitmapSubtitles::read(input, subtitle_idx, &clock, &video_dims)?;
+            return Ok(Some(Self::Bitmaps(subtitles)));
+        }
+        if filter::find("subtitles").is_none() {
+            log::warn!(
+                "ffmpeg was built without the subtitles filter, so the subtitles of {} can't be \
+                 burned in.",
+                path.display()
+            );
+            return Ok(None);
+        }
+        let position = input
+            .streams()
+            .filter(|s| s.index() < subtitle_idx)
+            .filter(|s| s.parameters().medium() == MediaType::Subtitle)
+            .count();
+        Ok(Some(Self::Filter(subtitles_filter_args(
+            path,
+            position,
+            &video_dims,
+        ))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn test_subtitles_filter_args() {
+        assert_eq!(
+            subtitles_filter_args(
+                Path::new("C:/Videos/Eve's.mkv"),
+                2,
+                &Dimensions::new(720, 480)
+            ),
+            "filename=C\\:/Videos/Eve\\'s.mkv:si=2:original_size=720x480"
+        );
+    }
+
+    #[test]
+    fn test_strip_markup() {
+        assert_eq!(strip_markup("{\\an8}Hello, <i>world</i>!"), "Hello, world!");
+        assert_eq!(
+            strip_markup("First line\\NSecond\\hline"),
+            "First line Second line"
+        );
+        assert_eq!(strip_markup("  Spaced   out  "), "Spaced out");
+    }
+
+    #[test]
+    fn test_parse_srt() {
+        let srt = "\u{feff}1\r\n00:00:01,000 --> 00:00:02,500\r\nHello,\r\n<b>world</b>\r\n\r\n\
+                   2\n00:01:00,000 --> 00:01:03,000 X1:10 X2:20\nSecond\n\n\
+                   3\nnot a time --> either\nSkipped\n";
+        assert_eq!(
+            parse_srt(srt),
+            vec![
+                TextCue::new(
+                    Timestamp::from_seconds(1.0),
+                    Some(Timestamp::from_seconds(2.5)),
+                    "Hello, world"
+                ),
+                TextCue::new(
+                    Timestamp::from_seconds(60.0),
+                    Some(Timestamp::from_seconds(63.0)),
+                    "Second"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_ass() {
+        let ass = "[Script Info]\nTitle: Test\n\n[Events]\n\
+                   Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+                   Dialogue: 0,0:00:01.50,0:00:03.00,Default,,0,0,0,,{\\i1}Hi, there{\\i0}\\Nfriend\n\
+                   Comment: 0,0:00:04.00,0:00:05.00,Default,,0,0,0,,Not shown\n\
+                   Dialogue: 0,bad,0:00:05.00,Default,,0,0,0,,Skipped\n";
+        assert_eq!(
+            parse_ass(ass),
+            vec![TextCue::new(
+                Timestamp::from_seconds(1.5),
+                Some(Timestamp::from_seconds(3.0)),
+                "Hi, there friend"
+            )]
+        );
+        // Events can have their fields in any order.
+        let ass =
+            "[Events]\nFormat: Start, End, Text\nDialogue: 0:00:02.00,0:00:04.00,Odd, order\n";
+        assert_eq!(parse_ass(ass)[0].text, "Odd, order");
+    }
+
+    #[test]
+    fn test_ass_event_text() {
+        assert_eq!(
+            ass_event_text("3,0,Default,,0,0,0,,Hello, world"),
+            "Hello, world"
+        );
+        assert_eq!(ass_event_text("Just text"), "Just text");
+    }
+
+    #[test]
+    fn test_text_at() {
+        let cue = |start: f64, end: Option<f64>, text: &str| {
+            TextCue::new(
+                Timestamp::from_seconds(start),
+                end.map(Timestamp::from_seconds),
+                text,
+            )
+        };
+        let subtitles = TextSubtitles::new(vec![
+            cue(10.0, None, "Last"),
+            cue(1.0, Some(3.0), "First"),
+            cue(2.0, Some(4.0), "Overlapping"),
+            cue(5.0, None, "Until the next"),
+            cue(6.0, Some(7.0), ""),
+        ]);
+        let text_at = |seconds: f64| subtitles.text_at(Timestamp::from_seconds(seconds));
+        assert_eq!(text_at(0.5), None);
+        assert_eq!(text_at(2.5), Some("First Overlapping".to_string()));
+        assert_eq!(text_at(3.5), Some("Overlapping".to_string()));
+        assert_eq!(text_at(4.5), None);
+        assert_eq!(text_at(9.0), Some("Until the next".to_string()));
+        assert_eq!(text_at(1000.0), Some("Last".to_string()));
+    }
+
+    #[test]
+    fn test_blend() {
+        let mut img = RgbImage::from_pixel(3, 1, Rgb([0, 0, 0]));
+        let mut overlay = RgbaImage::new(2, 1);
+        overlay.put_pixel(0, 0, Rgba([255, 255, 255, 255]));
+        overlay.put_pixel(1, 0, Rgba([255, 0, 0, 51]));
+        blend(&mut img, &overlay, 1, 0);
+        assert_eq!(*img.get_pixel(0, 0), Rgb([0, 0, 0]));
+        assert_eq!(*img.get_pixel(1, 0), Rgb([255, 255, 255]));
+        assert_eq!(*img.get_pixel(2, 0), Rgb([51, 0, 0]));
+        blend(&mut img, &overlay, -1, 0);
+        assert_eq!(*img.get_pixel(0, 0), Rgb([51, 0, 0]));
+    }
+
+    #[test]
+    fn test_draw_bitmap_subtitles() {
+        let cue = |start: f64, end: Option<f64>| BitmapCue {
+            start: Timestamp::from_seconds(start),
+            end: end.map(Timestamp::from_seconds),
+            x: 2,
+            y: 2,
+            image: RgbaImage::from_pixel(2, 2, Rgba([255, 255, 255, 255])),
+        };
+        let subtitles = BitmapSubtitles {
+            canvas: Dimensions::new(8, 8),
+            cues: vec![cue(1.0, Some(2.0)), cue(3.0, None)],
+        };
+        let draw_at = |seconds: f64| {
+            let mut img = RgbImage::new(4, 4);
+            subtitles.draw(&mut img, Timestamp::from_seconds(seconds));
+            *img.get_pixel(1, 1)
+        };
+        assert_eq!(draw_at(0.5), Rgb([0, 0, 0]));
+        assert_eq!(draw_at(1.5), Rgb([255, 255, 255]));
+        assert_eq!(draw_at(2.5), Rgb([0, 0, 0]));
+        assert_eq!(draw_at(100.0), Rgb([255, 255, 255]));
+    }
+
+    #[test]
+    fn test_read_text_subtitles_after_moving_input() {
+        ffmpeg::init().unwrap();
+        let path = std::env::temp_dir().join("mk-screens-test-read-subtitles.srt");
+        fs::write(
+            &path,
+            "1\n00:00:01,000 --> 00:00:02,000\nFirst\n\n\
+             2\n00:00:05,000 --> 00:00:06,000\nSecond\n",
+        )
+        .unwrap();
+        let mut input = ffmpeg::format::input(&path).unwrap();
+        // Reading the packets leaves the input at the end of the file, like generating keyframe
+        // capture times or detecting credits does for a video.
+        input.packets().for_each(drop);
+        let stream = input.stream(0).unwrap();
+        let clock = Clock::new(stream.time_base(), stream.start_time());
+        let subtitles = TextSubtitles::read(&mut input, 0, &clock).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            subtitles.text_at(Timestamp::from_seconds(1.5)).as_deref(),
+            Some("First")
+        );
+        assert_eq!(
+            subtitles.text_at(Timestamp::from_seconds(5.5)).as_deref(),
+            Some("Second")
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! Items for rendering text onto images using ffmpeg's `drawtext` filter.
+use ffmpeg::{
+    filter::{self, Graph},
+    format::Pixel as PixelFormat,
+    util::frame::video::Video,
+};
+use image::RgbImage;
+
+use crate::{
+    ffmpeg_ext::{LinkableGraph as _, ToImage as _},
+    util::Dimensions,
+    Error, Result,
+};
+
+/// Escapes a value so that it can be used as an option in a filter's argument string.
+pub fn escape_filter_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for chr in value.chars() {
+        if matches!(chr, '\\' | '\'' | ':') {
+            escaped.push('\\');
+        }
+        escaped.push(chr);
+    }
+    escaped
+}
+
+fn create_text_graph(dimensions: &Dimensions, text: &str) -> Result<Graph> {
+    let mut graph = Graph::new();
+    graph.add(
+        &filter::find("buffer").unwrap(),
+        "in",
+        &[
+            format!("video_size={}", dimensions),
+            format!(
+                "pix_fmt={}",
+                PixelFormat::RGB24.descriptor().unwrap().name()
+            ),
+            "time_base=1/1".to_string(),
+            "sar=1".to_string(),
+        ]
+        .join(":"),
+    )?;
+    graph.add(&filter::find("buffersink").unwrap(), "out", "")?;
+    graph.add(
+        &filter::find("drawtext").unwrap(),
+        "text",
+        &[
+            "x=(w-tw)/2".to_string(),
+            "y=(h-th)/2".to_string(),
+            "fontcolor=white".to_string(),
+            format!("fontsize={}", (dimensions.height() * 3 / 5).max(1)),
+            "expansion=none".to_string(),
+            format!("text={}", escape_filter_value(text)),
+        ]
+        .join(":"),
+    )?;
+    graph.chain_link(&["in", "text", "out"])?;
+    graph.validate()?;
+    Ok(graph)
+}
+
+/// Renders a line of white text centered on a black image of the provided size.
+pub fn render_text(dimensions: &Dimensions, text: &str) -> Result<RgbImage> {
+    let mut graph = create_text_graph(dimensions, text)?;
+    let mut background = Video::new(PixelFormat::RGB24, dimensions.width(), dimensions.height());
+    background.data_mut(0).fill(0);
+    graph.get("in").unwrap().source().add(&background)?;
+    let mut frame = Video::empty();
+    graph.get("out").unwrap().sink().frame(&mut frame)?;
+    frame.to_rgb_image().ok_or_else(|| Error::Other {
+        message: "Could not convert rendered text to an image".to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_filter_value() {
+        assert_eq!(escape_filter_value("Chapter 1"), "Chapter 1");
+        assert_eq!(escape_filter_value("Part 2: Eve's"), "Part 2\\: Eve\\'s");
+        assert_eq!(escape_filter_value("a\\b"), "a\\\\b");
+    }
+}
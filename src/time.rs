@@ -0,0 +1,415 @@
+//! Items for parsing user-provided times and durations.
+use std::{
+    fmt::{self, Display, Formatter},
+    fs,
+    path::Path,
+};
+
+use ffmpeg::{
+    ffi::{AV_NOPTS_VALUE, AV_TIME_BASE},
+    rescale::{self, Rescale as _},
+    Rational,
+};
+use serde::{
+    de::{self, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{files::timestamps_file_name, settings::Settings, Error, Result};
+
+/// A point in a video, in `AV_TIME_BASE` units from the start of the video. The first frame is
+/// always at zero, regardless of the start time of the container or stream (which is often far
+/// from zero in MPEG-TS files), so these are what the user sees. Use a [`Clock`] to convert to and
+/// from the timestamps that ffmpeg uses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(i64);
+
+impl Timestamp {
+    pub const ZERO: Self = Self(0);
+
+    pub fn new(av_time: i64) -> Self {
+        Self(av_time)
+    }
+
+    pub fn from_seconds(seconds: f64) -> Self {
+        Self((seconds * AV_TIME_BASE as f64) as i64)
+    }
+
+    /// The timestamp in `AV_TIME_BASE` units.
+    pub fn av_time(&self) -> i64 {
+        self.0
+    }
+
+    pub fn seconds(&self) -> f64 {
+        self.0 as f64 / AV_TIME_BASE as f64
+    }
+}
+
+impl Display for Timestamp {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let millis = self.0.max(0) / (AV_TIME_BASE as i64 / 1000);
+        write!(
+            f,
+            "{:02}:{:02}:{:02}.{:03}",
+            millis / 3_600_000,
+            millis / 60_000 % 60,
+            millis / 1000 % 60,
+            millis % 1000
+        )
+    }
+}
+
+/// Converts between [`Timestamp`]s and the timestamps of a stream (or of the container as a whole),
+/// which are in the stream's time base and offset by its start time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Clock {
+    time_base: Rational,
+    /// The start time, in `time_base` units.
+    start_time: i64,
+}
+
+impl Clock {
+    /// Creates a clock for a stream. An unknown (`AV_NOPTS_VALUE`) start time is treated as zero.
+    pub fn new(time_base: Rational, start_time: i64) -> Self {
+        let start_time = match start_time {
+            AV_NOPTS_VALUE => 0,
+            start_time => start_time,
+        };
+        Self {
+            time_base,
+            start_time,
+        }
+    }
+
+    /// Creates a clock for a container, whose timestamps are in `AV_TIME_BASE` units.
+    pub fn container(start_time: i64) -> Self {
+        Self::new(rescale::TIME_BASE, start_time)
+    }
+
+    pub fn time_base(&self) -> Rational {
+        self.time_base
+    }
+
+    /// The start time of the stream, in seconds.
+    pub fn start_seconds(&self) -> f64 {
+        self.start_time as f64 * self.time_base.numerator() as f64
+            / self.time_base.denominator() as f64
+    }
+
+    /// A clock with the same start time, but counting in a different time base. This is used to read
+    /// the timestamps of one stream relative to the start of another.
+    pub fn with_time_base(&self, time_base: Rational) -> Self {
+        Self {
+            time_base,
+            start_time: self.start_time.rescale(self.time_base, time_base),
+        }
+    }
+
+    /// Converts a timestamp into one for this clock.
+    pub fn to_clock(&self, timestamp: Timestamp) -> i64 {
+        timestamp.0.rescale(rescale::TIME_BASE, self.time_base) + self.start_time
+    }
+
+    /// Converts a timestamp from this clock.
+    pub fn from_clock(&self, timestamp: i64) -> Timestamp {
+        Timestamp((timestamp - self.start_time).rescale(self.time_base, rescale::TIME_BASE))
+    }
+}
+
+/// An amount of a video to trim, either as a percentage of the video's duration or as an absolute
+/// duration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrimAmount {
+    /// A percentage (from 0 to 100) of the video's duration.
+    Percent(f64),
+    /// A number of seconds.
+    Duration(f64),
+}
+
+impl TrimAmount {
+    /// Parses a trim amount. Plain numbers and numbers suffixed with a `%` are percentages, anything
+    /// else is parsed as a duration (see [`parse_duration`]).
+    pub fn parse(value: &str) -> Result<Self> {
+        let value = value.trim();
+        let percent = value.strip_suffix('%').unwrap_or(value);
+        if let Ok(percent) = percent.trim().parse::<f64>() {
+            return if (0.0..=100.0).contains(&percent) {
+                Ok(Self::Percent(percent))
+            } else {
+                Err(Error::InvalidTime {
+                    value: value.to_string(),
+                })
+            };
+        }
+        parse_duration(value)
+            .map(Self::Duration)
+            .ok_or_else(|| Error::InvalidTime {
+                value: value.to_string(),
+            })
+    }
+
+    /// The amount to trim from a video with the provided duration, in the same units as the
+    /// duration (`AV_TIME_BASE` units).
+    pub fn of(&self, duration: i64) -> i64 {
+        match *self {
+            Self::Percent(percent) => (duration as f64 * percent / 100.0) as i64,
+            Self::Duration(seconds) => (seconds * AV_TIME_BASE as f64) as i64,
+        }
+    }
+}
+
+impl Display for TrimAmount {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Percent(percent) => write!(f, "{}%", percent),
+            Self::Duration(seconds) => write!(f, "{}s", seconds),
+        }
+    }
+}
+
+impl Serialize for TrimAmount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+struct TrimAmountVisitor;
+
+impl Visitor<'_> for TrimAmountVisitor {
+    type Value = TrimAmount;
+
+    fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str("a percentage (e.g. 5 or 5%) or a duration (e.g. 90s or 1:30)")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> std::result::Result<TrimAmount, E> {
+        TrimAmount::parse(value).map_err(E::custom)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> std::result::Result<TrimAmount, E> {
+        self.visit_str(&value.to_string())
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> std::result::Result<TrimAmount, E> {
+        self.visit_str(&value.to_string())
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> std::result::Result<TrimAmount, E> {
+        self.visit_str(&value.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for TrimAmount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_any(TrimAmountVisitor)
+    }
+}
+
+/// A single point in a video where a frame should be captured, as provided by the user.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CaptureTime {
+    /// A time, in seconds, from the start of the video.
+    Seconds(f64),
+    /// A frame number, starting at 0.
+    Frame(u64),
+}
+
+impl CaptureTime {
+    /// Parses a single capture time. Frame numbers are suffixed with an `f` (e.g. `1200f`), anything
+    /// else is parsed as a duration (see [`parse_duration`]).
+    pub fn parse(value: &str) -> Result<Self> {
+        let value = value.trim();
+        if let Some(frame) = value.strip_suffix('f') {
+            if let Ok(frame) = frame.trim().parse() {
+                return Ok(Self::Frame(frame));
+            }
+        }
+        parse_duration(value)
+            .map(Self::Seconds)
+            .ok_or_else(|| Error::InvalidTime {
+                value: value.to_string(),
+            })
+    }
+}
+
+/// Parses a duration into a number of seconds. Durations can be provided either as:
+/// - A plain number of seconds (`90` or `90.5`).
+/// - A number with units, which can be combined (`90s`, `8m`, `1h30m`, `1m2.5s`).
+/// - A clock-style timestamp (`01:30`, `1:02:03.5`).
+pub fn parse_duration(value: &str) -> Option<f64> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    if value.contains(':') {
+        let parts: Vec<&str> = value.split(':').collect();
+        if parts.len() > 3 {
+            return None;
+        }
+        return parts.iter().try_fold(0.0, |total, part| {
+            let part: f64 = part.parse().ok()?;
+            (part >= 0.0).then_some(total * 60.0 + part)
+        });
+    }
+    if let Ok(seconds) = value.parse::<f64>() {
+        return (seconds >= 0.0).then_some(seconds);
+    }
+    let mut total = 0.0;
+    let mut number = String::new();
+    for chr in value.chars() {
+        let multiplier = match chr {
+            '0'..='9' | '.' => {
+                number.push(chr);
+                continue;
+            }
+            'h' => 3600.0,
+            'm' => 60.0,
+            's' => 1.0,
+            _ => return None,
+        };
+        total += number.parse::<f64>().ok()? * multiplier;
+        number.clear();
+    }
+    if number.is_empty() {
+        Some(total)
+    } else {
+        None
+    }
+}
+
+/// Parses a list of capture times. Times can be separated by commas or whitespace, and anything
+/// after a `#` on a line is ignored.
+pub fn parse_capture_times(text: &str) -> Result<Vec<CaptureTime>> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .flat_map(|line| line.split(|c: char| c == ',' || c.is_whitespace()))
+        .filter(|value| !value.is_empty())
+        .map(CaptureTime::parse)
+        .collect()
+}
+
+fn read_capture_times_file<P: AsRef<Path>>(path: P) -> Result<Vec<CaptureTime>> {
+    parse_capture_times(&fs::read_to_string(path)?)
+}
+
+/// Gets the explicit list of times to capture for a video file, if one was provided. The times
+/// passed on the command line take precedence, followed by the times file from the settings, and
+/// finally the video's own sidecar file (the video's file name suffixed with `.timestamps`).
+pub fn explicit_capture_times<P>(
+    settings: &Settings,
+    video_path: P,
+) -> Result<Option<Vec<CaptureTime>>>
+where
+    P: AsRef<Path>,
+{
+    if !settings.timestamps().is_empty() {
+        return Ok(Some(
+            settings
+                .timestamps()
+                .iter()
+                .map(|value| CaptureTime::parse(value))
+                .collect::<Result<_>>()?,
+        ));
+    }
+    if let Some(path) = settings.timestamps_file() {
+        return read_capture_times_file(path).map(Some);
+    }
+    let video_path = video_path.as_ref();
+    let sidecar = video_path.with_file_name(timestamps_file_name(&video_path));
+    if sidecar.is_file() {
+        log::debug!("Using timestamps from {}", sidecar.display());
+        return read_capture_times_file(sidecar).map(Some);
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock() {
+        let clock = Clock::new(Rational::new(1, 90_000), 126_000);
+        assert_eq!(clock.start_seconds(), 1.4);
+        assert_eq!(clock.to_clock(Timestamp::ZERO), 126_000);
+        assert_eq!(clock.to_clock(Timestamp::from_seconds(2.0)), 306_000);
+        assert_eq!(clock.from_clock(306_000), Timestamp::from_seconds(2.0));
+        let millis = clock.with_time_base(Rational::new(1, 1000));
+        assert_eq!(millis.to_clock(Timestamp::ZERO), 1400);
+        assert_eq!(millis.from_clock(3400), Timestamp::from_seconds(2.0));
+        let container = Clock::container(AV_NOPTS_VALUE);
+        assert_eq!(container.to_clock(Timestamp::new(5)), 5);
+        assert_eq!(Timestamp::from_seconds(3723.5).to_string(), "01:02:03.500");
+    }
+
+    #[test]
+    fn test_clock_with_earlier_container_start() {
+        // An MPEG-TS file whose audio starts at 1s and whose video starts at 1.4s, so the container
+        // starts at 1s.
+        let video = Clock::new(Rational::new(1, 90_000), 126_000);
+        let container = Clock::container(1_000_000);
+        let av_time = video.with_time_base(rescale::TIME_BASE);
+        // The start of the video is where the video stream starts, not the container.
+        assert_eq!(av_time.to_clock(Timestamp::ZERO), 1_400_000);
+        assert_ne!(
+            av_time.to_clock(Timestamp::ZERO),
+            container.to_clock(Timestamp::ZERO)
+        );
+        assert_eq!(av_time.from_clock(3_400_000), video.from_clock(306_000));
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90"), Some(90.0));
+        assert_eq!(parse_duration("90.5"), Some(90.5));
+        assert_eq!(parse_duration("90s"), Some(90.0));
+        assert_eq!(parse_duration("8m"), Some(480.0));
+        assert_eq!(parse_duration("1h30m"), Some(5400.0));
+        assert_eq!(parse_duration("1m2.5s"), Some(62.5));
+        assert_eq!(parse_duration("01:30"), Some(90.0));
+        assert_eq!(parse_duration("1:02:03.5"), Some(3723.5));
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("-5"), None);
+        assert_eq!(parse_duration("5x"), None);
+        assert_eq!(parse_duration("5m3"), None);
+        assert_eq!(parse_duration("1:2:3:4"), None);
+    }
+
+    #[test]
+    fn test_parse_trim_amount() {
+        assert_eq!(TrimAmount::parse("5").unwrap(), TrimAmount::Percent(5.0));
+        assert_eq!(TrimAmount::parse("2.5%").unwrap(), TrimAmount::Percent(2.5));
+        assert_eq!(
+            TrimAmount::parse("90s").unwrap(),
+            TrimAmount::Duration(90.0)
+        );
+        assert_eq!(
+            TrimAmount::parse("8m").unwrap(),
+            TrimAmount::Duration(480.0)
+        );
+        assert_eq!(
+            TrimAmount::parse("1:30").unwrap(),
+            TrimAmount::Duration(90.0)
+        );
+        assert!(TrimAmount::parse("101%").is_err());
+        assert!(TrimAmount::parse("soon").is_err());
+        assert_eq!(TrimAmount::Percent(10.0).of(2_000_000), 200_000);
+        assert_eq!(TrimAmount::Duration(1.5).of(2_000_000), 1_500_000);
+    }
+
+    #[test]
+    fn test_parse_capture_times() {
+        let times =
+            parse_capture_times("0:30, 90s\n1200f # the explosion\n# comment\n\n5").unwrap();
+        assert_eq!(
+            times,
+            vec![
+                CaptureTime::Seconds(30.0),
+                CaptureTime::Seconds(90.0),
+                CaptureTime::Frame(1200),
+                CaptureTime::Seconds(5.0),
+            ]
+        );
+        assert!(parse_capture_times("10, bad").is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use crate::Result;
 use ffmpeg::util::log::Level as FfmpegLogLevel;
 use filetime::{set_file_mtime, FileTime};
+use image::{imageops, imageops::FilterType, RgbImage};
 use std::{
     env,
     fmt::{self, Display, Formatter},
@@ -15,7 +16,7 @@ lazy_static::lazy_static! {
     pub static ref ENV: EnvVars = EnvVars::default();
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Dimensions(pub u32, pub u32);
 
 impl Dimensions {
@@ -94,6 +95,26 @@ pub fn safe_string_truncate(s: &str, target_size: usize) -> String {
     s
 }
 
+/// Computes a perceptual hash (a difference hash) of an image. Images that look alike have hashes
+/// that differ by only a few bits, regardless of their size.
+pub fn perceptual_hash(img: &RgbImage) -> u64 {
+    let small = imageops::resize(&imageops::grayscale(img), 9, 8, FilterType::Triangle);
+    small
+        .enumerate_pixels()
+        .filter(|(x, _, _)| *x < 8)
+        .fold(0, |hash, (x, y, pixel)| {
+            (hash << 1) | (pixel.0[0] < small.get_pixel(x + 1, y).0[0]) as u64
+        })
+}
+
+/// The average luma (from 0 to 255) of an image. Used alongside [`perceptual_hash`], which only
+/// looks at differences within an image and so is the same for any flat image, however bright.
+pub fn mean_luma(img: &RgbImage) -> f64 {
+    let grey = imageops::grayscale(img);
+    let total: u64 = grey.pixels().map(|p| p.0[0] as u64).sum();
+    total as f64 / (grey.width() as u64 * grey.height() as u64).max(1) as f64
+}
+
 #[derive(Debug, Default)]
 pub struct EnvVars {
     hide_progress_bars: OnceLock<bool>,
@@ -159,6 +180,29 @@ mod tests {
     use super::*;
     use std::env::set_var;
 
+    #[test]
+    fn test_perceptual_hash() {
+        let gradient = RgbImage::from_fn(90, 80, |x, _| image::Rgb([x as u8, x as u8, x as u8]));
+        let reversed =
+            RgbImage::from_fn(90, 80, |x, _| image::Rgb([255 - x as u8, 255 - x as u8, 0]));
+        assert_eq!(perceptual_hash(&gradient), u64::MAX);
+        assert_eq!(perceptual_hash(&reversed), 0);
+        let bigger = imageops::resize(&gradient, 180, 160, FilterType::Nearest);
+        assert_eq!(perceptual_hash(&gradient), perceptual_hash(&bigger));
+    }
+
+    #[test]
+    fn test_mean_luma() {
+        let black = RgbImage::new(8, 8);
+        let white = RgbImage::from_pixel(8, 8, image::Rgb([255, 255, 255]));
+        // Flat images all have the same hash, but not the same luma.
+        assert_eq!(perceptual_hash(&black), perceptual_hash(&white));
+        assert_eq!(mean_luma(&black), 0.0);
+        assert_eq!(mean_luma(&white), 255.0);
+        let half = RgbImage::from_fn(8, 8, |x, _| image::Rgb([255 * (x % 2) as u8; 3]));
+        assert_eq!(mean_luma(&half), 127.5);
+    }
+
     #[test]
     fn test_envvar_to_bool() {
         set_var("MK_SCREENS_TEST_VAR", "1");
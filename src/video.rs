@@ -1,32 +1,52 @@
 //! Items relating to video files.
 
 use std::{
-    iter::repeat,
+    fs,
+    iter::{once, repeat},
+    mem,
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
 use derivative::Derivative;
 use ffmpeg::{
     codec::context::Context as CodecContext,
     decoder::Video as VideoDecoder,
+    ffi::{AV_NOPTS_VALUE, AV_TIME_BASE},
     filter::{self, Graph},
-    format::{context::Input, stream::Stream, Pixel as PixelFormat},
-    util::{dictionary::Owned as FfmpegDictionary, frame::video::Video, media::Type as MediaType},
-    Rational,
+    format::{
+        context::Input,
+        stream::{Disposition, Stream},
+        Pixel as PixelFormat,
+    },
+    rescale::{self, Rescale as _},
+    util::{
+        color::{Range as ColorRange, Space as ColorSpace, TransferCharacteristic},
+        dictionary::Owned as FfmpegDictionary,
+        error::Error as FfmpegError,
+        frame::video::Video,
+        media::Type as MediaType,
+    },
+    Discard, Rational,
 };
+use image::RgbImage;
 
 use crate::{
-    ffmpeg_ext::LinkableGraph as _,
+    detect,
+    ffmpeg_ext::{
+        cover_art, FrameSeekable as _, HasDimensions as _, HasDisplayDimensions as _,
+        LinkableGraph as _, SeekFlags, ToImage as _,
+    },
     files::img_file_name,
-    settings::Settings,
+    settings::{CaptureMode, ScaleQuality, SeekMode, Settings, StreamSelector, ToneMapAlgorithm},
+    subtitles::{BitmapSubtitles, SubtitleBurnIn, TextSubtitles},
+    time::{CaptureTime, Clock, Timestamp},
     util::{Dimensions, ENV},
-    Error, Result,
+    waveform, Error, Result,
 };
 
-const BACK_TRIM_AMOUNT: f64 = 0.01;
-
 #[inline]
-fn input_opts<'a>() -> FfmpegDictionary<'a> {
+pub(crate) fn input_opts<'a>() -> FfmpegDictionary<'a> {
     let mut opts = FfmpegDictionary::new();
     opts.set("probesize", ENV.ffmpeg_probesize());
     opts.set("analyzeduration", ENV.ffmpeg_analyzeduration());
@@ -40,17 +60,111 @@ fn format_rational(rational: &Rational) -> String {
     }
 }
 
+/// The name of the scale filter's color matrix for a colorspace. Unspecified colorspaces are guessed
+/// from the height of the video, the same way most players do: HD video is BT.709, and anything
+/// smaller is BT.601.
+fn color_matrix_name(space: ColorSpace, height: u32) -> &'static str {
+    match space {
+        ColorSpace::BT709 => "bt709",
+        ColorSpace::BT470BG | ColorSpace::SMPTE170M => "bt601",
+        ColorSpace::SMPTE240M => "smpte240m",
+        ColorSpace::FCC => "fcc",
+        ColorSpace::BT2020NCL | ColorSpace::BT2020CL => "bt2020",
+        _ if height >= 720 => "bt709",
+        _ => "bt601",
+    }
+}
+
+/// The name of the scale filter's color range for a range. Unspecified ranges are treated as
+/// limited, since that's what nearly all video is.
+fn color_range_name(range: ColorRange) -> &'static str {
+    match range {
+        ColorRange::JPEG => "full",
+        ColorRange::MPEG | ColorRange::Unspecified => "limited",
+    }
+}
+
+/// The buffer source options that describe the colors of the video's frames.
+fn buffer_color_args(space: ColorSpace, range: ColorRange) -> Vec<String> {
+    [
+        space.name().map(|name| format!("colorspace={}", name)),
+        range.name().map(|name| format!("range={}", name)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// The scale filter options that convert frames with the provided colors to full range RGB.
+fn scale_color_args(space: ColorSpace, range: ColorRange, height: u32) -> Vec<String> {
+    vec![
+        format!("in_color_matrix={}", color_matrix_name(space, height)),
+        format!("in_range={}", color_range_name(range)),
+        "out_range=full".to_string(),
+    ]
+}
+
+/// Whether a video uses one of the HDR transfer characteristics.
+fn is_hdr(decoder: &VideoDecoder) -> bool {
+    matches!(
+        decoder.color_transfer_characteristic(),
+        TransferCharacteristic::SMPTE2084 | TransferCharacteristic::ARIB_STD_B67
+    )
+}
+
+/// Adds the filters that tone map HDR frames down to SDR (BT.709) to the graph, returning their
+/// names in the order they should be linked. Nothing is added if the video isn't HDR, tone mapping
+/// is turned off, or ffmpeg was built without `zscale`.
+fn add_tonemap_filters(
+    graph: &mut Graph,
+    decoder: &VideoDecoder,
+    algorithm: ToneMapAlgorithm,
+) -> Result<Vec<&'static str>> {
+    let Some(algorithm) = algorithm.ffmpeg_name() else {
+        return Ok(vec![]);
+    };
+    if !is_hdr(decoder) {
+        return Ok(vec![]);
+    }
+    let Some(zscale) = filter::find("zscale") else {
+        log::warn!(
+            "ffmpeg was built without the zscale filter, so HDR videos can't be tone mapped."
+        );
+        return Ok(vec![]);
+    };
+    // Tone mapping has to happen on linear light, in floating point.
+    graph.add(&zscale, "tm_linear", "t=linear:npl=100")?;
+    graph.add(&filter::find("format").unwrap(), "tm_float", "gbrpf32le")?;
+    graph.add(&zscale, "tm_primaries", "p=bt709")?;
+    graph.add(
+        &filter::find("tonemap").unwrap(),
+        "tonemap",
+        &format!("tonemap={}:desat=0", algorithm),
+    )?;
+    graph.add(&zscale, "tm_sdr", "t=bt709:m=bt709:r=tv")?;
+    Ok(vec![
+        "tm_linear",
+        "tm_float",
+        "tm_primaries",
+        "tonemap",
+        "tm_sdr",
+    ])
+}
+
 fn create_filter_graph(
     decoder: &VideoDecoder,
-    stream: &Stream,
+    clock: &Clock,
     out_dims: &Dimensions,
+    tonemap: ToneMapAlgorithm,
+    scale_quality: ScaleQuality,
+    subtitles_filter: Option<&str>,
 ) -> Result<Graph> {
     let mut graph = Graph::new();
     let mut buffer_args = vec![
         format!("width={}", decoder.width()),
         format!("height={}", decoder.height()),
         format!("video_size={}x{}", decoder.width(), decoder.height()),
-        format!("time_base={}", stream.time_base()),
+        format!("time_base={}", clock.time_base()),
         format!("sar={}", format_rational(&decoder.aspect_ratio())),
     ];
     if let Some(frame_rate) = &decoder.frame_rate() {
@@ -59,6 +173,10 @@ fn create_filter_graph(
     if let Some(desc) = decoder.format().descriptor() {
         buffer_args.push(format!("pix_fmt={}", desc.name()));
     }
+    buffer_args.extend(buffer_color_args(
+        decoder.color_space(),
+        decoder.color_range(),
+    ));
     graph.add(
         &ffmpeg::filter::find("buffer").unwrap(),
         "in",
@@ -73,7 +191,9 @@ fn create_filter_graph(
         "box=1".to_string(),
         "boxcolor=black".to_string(),
         format!("boxborderw={}", out_dims.height() / 45),
-        "text=%{pts\\:hms}".to_string(),
+        // The frames keep the stream's timestamps, so the start time is subtracted to show the time
+        // from the start of the video.
+        format!("text=%{{pts\\:hms\\:{}}}", -clock.start_seconds()),
     ]
     .join(":");
     graph.add(
@@ -82,29 +202,288 @@ fn create_filter_graph(
         PixelFormat::RGB24.descriptor().unwrap().name(),
     )?;
     graph.add(&filter::find("drawtext").unwrap(), "btc", &drawtext_args)?;
+    // The captures are scaled to their display size, so their pixels are square from here on.
+    graph.add(&filter::find("setsar").unwrap(), "setsar", "1")?;
+    let tonemap_filters = add_tonemap_filters(&mut graph, decoder, tonemap)?;
+    let mut scale_args = vec![
+        format!("w={}", out_dims.width()),
+        format!("h={}", out_dims.height()),
+        "eval=frame".to_string(),
+        format!("flags={}", scale_quality.ffmpeg_name()),
+    ];
+    // The scaler does the conversion to RGB (since it's followed by the format filter), so it
+    // needs to know the video's colors. Tone mapped frames are already converted.
+    if tonemap_filters.is_empty() {
+        scale_args.extend(scale_color_args(
+            decoder.color_space(),
+            decoder.color_range(),
+            decoder.height(),
+        ));
+    }
     graph.add(
         &filter::find("scale").unwrap(),
         "scale",
-        &[
-            format!("w={}", out_dims.width()),
-            format!("h={}", out_dims.height()),
-            "eval=frame".to_string(),
-            "flags=fast_bilinear".to_string(),
-        ]
-        .join(":"),
+        &scale_args.join(":"),
     )?;
-    graph.chain_link(&["in", "pix_fmt", "scale", "btc", "out"])?;
+    let mut chain = vec!["in"];
+    chain.extend(tonemap_filters);
+    chain.extend(["scale", "pix_fmt", "setsar"]);
+    if let Some(subtitles_args) = subtitles_filter {
+        // `SubtitleBurnIn::open` only uses the filter if ffmpeg has it.
+        let subtitles = filter::find("subtitles").ok_or_else(|| Error::NoSuchFilter {
+            filter_name: "subtitles".to_string(),
+        })?;
+        graph.add(&subtitles, "subs", subtitles_args)?;
+        chain.push("subs");
+    }
+    chain.extend(["btc", "out"]);
+    graph.chain_link(&chain)?;
     graph.validate()?;
     Ok(graph)
 }
 
-pub fn find_best_stream<P: AsRef<Path>>(input: &Input, path: P) -> Result<Stream> {
+#[inline]
+fn decoder_opts<'a>(settings: &Settings) -> FfmpegDictionary<'a> {
+    let mut opts = FfmpegDictionary::new();
+    match settings.decoder_threads() {
+        Some(threads) => opts.set("threads", &threads.to_string()),
+        None => opts.set("threads", "auto"),
+    }
+    opts.set("thread_type", settings.decoder_thread_type().ffmpeg_name());
+    opts
+}
+
+/// Opens a decoder for the provided stream.
+fn open_decoder(settings: &Settings, stream: &Stream) -> Result<VideoDecoder> {
+    let mut decoder = CodecContext::from_parameters(stream.parameters())?.decoder();
+    if settings.skip_nonkey_frames() {
+        decoder.skip_frame(Discard::NonKey);
+    }
+    let codec = ffmpeg::decoder::find(decoder.id()).ok_or(FfmpegError::DecoderNotFound)?;
+    Ok(decoder
+        .open_as_with(codec, decoder_opts(settings))?
+        .video()?)
+}
+
+pub(crate) fn stream_language(stream: &Stream) -> Option<String> {
+    stream.metadata().get("language").map(str::to_lowercase)
+}
+
+fn stream_area(stream: &Stream) -> u64 {
+    let dimensions = stream.parameters().dimensions();
+    dimensions.width() as u64 * dimensions.height() as u64
+}
+
+/// Picks the best stream out of the candidates: the one ffmpeg would pick if it's a candidate, and
+/// otherwise the default stream, with ties going to the largest.
+fn best_of<'a>(input: &Input, candidates: Vec<Stream<'a>>) -> Option<Stream<'a>> {
+    let ffmpeg_best = input.streams().best(MediaType::Video).map(|s| s.index());
+    if let Some(idx) = candidates
+        .iter()
+        .position(|s| Some(s.index()) == ffmpeg_best)
+    {
+        return candidates.into_iter().nth(idx);
+    }
+    candidates.into_iter().max_by_key(|s| {
+        (
+            s.disposition().contains(Disposition::DEFAULT),
+            stream_area(s),
+        )
+    })
+}
+
+/// Finds the video stream to capture. Attached pictures (like cover art) are never picked, unless
+/// asked for by index.
+pub fn find_video_stream<'a, P: AsRef<Path>>(
+    input: &'a Input,
+    path: P,
+    selector: &StreamSelector,
+) -> Result<Stream<'a>> {
+    let path = path.as_ref();
+    let is_video = |s: &Stream| s.parameters().medium() == MediaType::Video;
+    let candidates = || {
+        input
+            .streams()
+            .filter(|s| is_video(s) && !s.disposition().contains(Disposition::ATTACHED_PIC))
+    };
+    let stream = match selector {
+        StreamSelector::Best => best_of(input, candidates().collect()),
+        StreamSelector::Largest => candidates().max_by_key(stream_area),
+        StreamSelector::Index(idx) => input.stream(*idx).filter(is_video),
+        StreamSelector::Language(language) => best_of(
+            input,
+            candidates()
+                .filter(|s| stream_language(s).as_ref() == Some(language))
+                .collect(),
+        ),
+    };
+    match (stream, selector) {
+        (Some(stream), _) => Ok(stream),
+        (None, StreamSelector::Best) => Err(Error::NoVideoStream {
+            path: path.to_path_buf(),
+        }),
+        (None, selector) => Err(Error::NoSuchStream {
+            path: path.to_path_buf(),
+            selector: selector.to_string(),
+        }),
+    }
+}
+
+/// Whether a frame decoded after seeking shows that seeking doesn't work for a video, so it has to
+/// be decoded sequentially instead: either nothing was decoded, or accurate seeking landed on the
+/// same frame as the last capture. Fast seeking lands on the same keyframe whenever captures are
+/// closer together than the keyframes, so a repeated frame doesn't say anything about it; those
+/// duplicates are retried with accurate seeking instead.
+pub(crate) fn is_bad_seek(seek_mode: SeekMode, got_frame: bool, repeated: bool) -> bool {
+    !got_frame || (repeated && seek_mode == SeekMode::Accurate)
+}
+
+/// Describes what went wrong when seeking, for the log.
+fn seek_problem(result: &Result<Video>) -> String {
+    match result {
+        Err(error) => format!("failed ({})", error),
+        Ok(frame) if frame.width() == 0 => "produced no frame".to_string(),
+        Ok(_) => "produced a repeated frame".to_string(),
+    }
+}
+
+/// How much of the end of a file to read when looking for the last timestamp of a stream.
+const DURATION_PROBE_BYTES: i64 = 4 * 1024 * 1024;
+
+#[inline]
+fn valid_duration(duration: i64) -> Option<i64> {
+    (duration > 0 && duration != AV_NOPTS_VALUE).then_some(duration)
+}
+
+/// Finds the duration of the last timestamp of a stream, by reading the packets at the end of the
+/// file. The input is seeked back to the start afterwards.
+fn last_packet_duration(input: &mut Input, stream_idx: usize) -> Option<i64> {
+    let stream = input.stream(stream_idx)?;
+    let time_base = stream.time_base();
+    let start_time = match stream.start_time() {
+        AV_NOPTS_VALUE => 0,
+        start_time => start_time,
+    };
+    let file_size = unsafe { ffmpeg::ffi::avio_size((*input.as_ptr()).pb) };
+    if file_size <= 0 {
+        return None;
+    }
+    let offset = (file_size - DURATION_PROBE_BYTES).max(0);
     input
-        .streams()
-        .find(|s| s.parameters().medium() == MediaType::Video)
-        .ok_or_else(|| Error::NoVideoStream {
-            path: path.as_ref().to_path_buf(),
-        })
+        .seek_to_frame(-1, offset, SeekFlags::BYTE | SeekFlags::ANY)
+        .ok()?;
+    let end = input
+        .packets()
+        .filter(|(s, _)| s.index() == stream_idx)
+        .filter_map(|(_, p)| p.pts().or_else(|| p.dts()).map(|ts| ts + p.duration()))
+        .max();
+    if input.seek_to_frame(-1, 0, SeekFlags::BYTE).is_err() {
+        log::debug!("Could not seek back to the start after probing the duration.");
+    }
+    end.map(|end| (end - start_time).rescale(time_base, rescale::TIME_BASE))
+        .and_then(valid_duration)
+}
+
+/// Determines the duration of a file, in `AV_TIME_BASE` units. Many files (particularly streams
+/// like MPEG-TS) don't have a container duration, so this falls back to:
+/// 1. The duration of the provided stream.
+/// 2. The timestamp of the last packet of the provided stream.
+/// 3. An estimate from the file's size and bitrate.
+pub(crate) fn probe_duration<P: AsRef<Path>>(
+    input: &mut Input,
+    stream_idx: usize,
+    path: P,
+) -> Result<i64> {
+    if let Some(duration) = valid_duration(input.duration()) {
+        return Ok(duration);
+    }
+    let path = path.as_ref();
+    log::debug!("{} has no container duration", path.display());
+    let stream_duration = input.stream(stream_idx).and_then(|stream| {
+        valid_duration(stream.duration())
+            .map(|duration| duration.rescale(stream.time_base(), rescale::TIME_BASE))
+    });
+    if let Some(duration) = stream_duration {
+        return Ok(duration);
+    }
+    if let Some(duration) = last_packet_duration(input, stream_idx) {
+        log::debug!(
+            "Using the last packet's timestamp as the duration of {}",
+            path.display()
+        );
+        return Ok(duration);
+    }
+    let bit_rate = input.bit_rate();
+    if bit_rate > 0 {
+        let file_size = fs::metadata(path)?.len() as f64;
+        let duration = (file_size * 8.0 / bit_rate as f64 * AV_TIME_BASE as f64) as i64;
+        if let Some(duration) = valid_duration(duration) {
+            log::warn!(
+                "Estimating the duration of {} from its bitrate; capture times may be inaccurate.",
+                path.display()
+            );
+            return Ok(duration);
+        }
+    }
+    Err(Error::NoDuration {
+        path: path.to_path_buf(),
+    })
+}
+
+/// A chapter of a video, with its start and end times in `AV_TIME_BASE` units from the start of the
+/// video.
+#[derive(Debug, Clone)]
+pub struct ChapterInfo {
+    title: Option<String>,
+    start: i64,
+    end: i64,
+}
+
+impl ChapterInfo {
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn start(&self) -> i64 {
+        self.start
+    }
+
+    pub fn end(&self) -> i64 {
+        self.end
+    }
+}
+
+/// Finds the chapter that contains the provided timestamp.
+pub fn chapter_at(chapters: &[ChapterInfo], timestamp: Timestamp) -> Option<&ChapterInfo> {
+    let timestamp = timestamp.av_time();
+    chapters
+        .iter()
+        .find(|chapter| chapter.start() <= timestamp && timestamp < chapter.end())
+}
+
+/// Splits `num_captures` captures between ranges of the provided lengths, proportionally to their
+/// length. Every range gets at least one capture, even if that means going over `num_captures`.
+fn distribute_captures(lengths: &[i64], num_captures: usize) -> Vec<usize> {
+    let total_length: i64 = lengths.iter().sum();
+    let extra = num_captures.saturating_sub(lengths.len());
+    if total_length <= 0 {
+        return vec![1; lengths.len()];
+    }
+    let shares: Vec<f64> = lengths
+        .iter()
+        .map(|length| extra as f64 * *length as f64 / total_length as f64)
+        .collect();
+    let mut counts: Vec<usize> = shares.iter().map(|share| 1 + *share as usize).collect();
+    // Hand out whatever is left over to the ranges with the largest remainders.
+    let mut by_remainder: Vec<usize> = (0..lengths.len()).collect();
+    by_remainder.sort_by(|a, b| (shares[*b] % 1.0).total_cmp(&(shares[*a] % 1.0)));
+    let assigned: usize = counts.iter().sum();
+    let leftover = (lengths.len() + extra).saturating_sub(assigned);
+    by_remainder
+        .into_iter()
+        .take(leftover)
+        .for_each(|idx| counts[idx] += 1);
+    counts
 }
 
 #[derive(Derivative)]
@@ -116,54 +495,351 @@ pub struct VidInfo {
     pixel_format: PixelFormat,
     dimensions: Dimensions,
     capture_dimensions: Dimensions,
-    interval: i64,
+    frame_rate: Rational,
+    seek_mode: SeekMode,
+    /// Converts to and from the video stream's timestamps.
+    clock: Clock,
+    /// The timestamp of the last frame that was captured.
+    last_frame_ts: Option<i64>,
+    /// When decoding sequentially, the last target timestamp and the frame that was used for it.
+    #[derivative(Debug = "ignore")]
+    sequential_state: Option<(i64, Video)>,
+    /// The arguments of the `subtitles` filter that burns text subtitles into the captures.
+    subtitles_filter: Option<String>,
+    /// Subtitles to draw onto the captures that ffmpeg's `subtitles` filter can't draw. They're
+    /// shared with any capture workers, so that they're only read from the file once.
+    #[derivative(Debug = "ignore")]
+    bitmap_subtitles: Option<Arc<BitmapSubtitles>>,
     video_stream_idx: usize,
     #[derivative(Debug = "ignore")]
     input: Input,
     #[derivative(Debug = "ignore")]
+    decoder: VideoDecoder,
+    #[derivative(Debug = "ignore")]
     filter: Graph,
 }
 
 impl VidInfo {
     pub fn new<P: AsRef<Path>>(settings: &Settings, path: P) -> Result<Self> {
-        let input = ffmpeg::format::input_with_dictionary(&path, input_opts())?;
-        let stream = find_best_stream(&input, &path)?;
-        let decoder = CodecContext::from_parameters(stream.parameters())?
-            .decoder()
-            .video()?;
-        let dimensions = Dimensions::new(decoder.width(), decoder.height());
+        Self::with_stream(settings, path, &settings.video_stream())
+    }
+
+    /// Gets the information for a specific video stream of a file.
+    pub fn with_stream<P: AsRef<Path>>(
+        settings: &Settings,
+        path: P,
+        selector: &StreamSelector,
+    ) -> Result<Self> {
+        let mut input = ffmpeg::format::input_with_dictionary(&path, input_opts())?;
+        let stream_idx = find_video_stream(&input, &path, selector)?.index();
+        let duration = probe_duration(&mut input, stream_idx, &path)?;
+        let burn_in = if settings.burn_subtitles() {
+            SubtitleBurnIn::open(
+                &mut input,
+                path.as_ref(),
+                settings.subtitle_language(),
+                stream_idx,
+            )?
+        } else {
+            None
+        };
+        let (subtitles_filter, bitmap_subtitles) = match burn_in {
+            Some(SubtitleBurnIn::Filter(args)) => (Some(args), None),
+            Some(SubtitleBurnIn::Bitmaps(subtitles)) => (None, Some(Arc::new(subtitles))),
+            None => (None, None),
+        };
+        Self::from_input(
+            settings,
+            path,
+            input,
+            stream_idx,
+            duration,
+            subtitles_filter,
+            bitmap_subtitles,
+        )
+    }
+
+    /// Opens the file again for another capture worker. The duration and the subtitles to burn in
+    /// are taken from this one instead of being read from the file again.
+    pub fn worker(&self, settings: &Settings) -> Result<Self> {
+        let input = ffmpeg::format::input_with_dictionary(&self.path, input_opts())?;
+        let mut worker = Self::from_input(
+            settings,
+            &self.path,
+            input,
+            self.video_stream_idx,
+            self.duration,
+            self.subtitles_filter.clone(),
+            self.bitmap_subtitles.clone(),
+        )?;
+        worker.seek_mode = self.seek_mode;
+        Ok(worker)
+    }
+
+    /// Opens another video stream of the same file, for showing the streams side by side. Bitmap
+    /// subtitles are shared instead of being read from the file again; text subtitles are found
+    /// again, which doesn't need to read the file, since they're sized for the video stream.
+    pub fn other_stream(&self, settings: &Settings, stream_idx: usize) -> Result<Self> {
+        let mut input = ffmpeg::format::input_with_dictionary(&self.path, input_opts())?;
+        let duration = probe_duration(&mut input, stream_idx, &self.path)?;
+        let subtitles_filter = match &self.subtitles_filter {
+            Some(_) => match SubtitleBurnIn::open(
+                &mut input,
+                &self.path,
+                settings.subtitle_language(),
+                stream_idx,
+            )? {
+                Some(SubtitleBurnIn::Filter(args)) => Some(args),
+                _ => None,
+            },
+            None => None,
+        };
+        Self::from_input(
+            settings,
+            &self.path,
+            input,
+            stream_idx,
+            duration,
+            subtitles_filter,
+            self.bitmap_subtitles.clone(),
+        )
+    }
+
+    /// Sets up the decoder and filters for a stream of an already opened file.
+    fn from_input<P: AsRef<Path>>(
+        settings: &Settings,
+        path: P,
+        input: Input,
+        stream_idx: usize,
+        duration: i64,
+        subtitles_filter: Option<String>,
+        bitmap_subtitles: Option<Arc<BitmapSubtitles>>,
+    ) -> Result<Self> {
+        let stream = input.stream(stream_idx).unwrap();
+        let decoder = open_decoder(settings, &stream)?;
+        let dimensions = decoder.dimensions();
+        let aspect_dimensions = if settings.storage_aspect() {
+            dimensions.clone()
+        } else {
+            decoder.display_dimensions()
+        };
         let mut capture_width = (settings.width() - (settings.columns() * 4)) / settings.columns();
-        if !settings.scale_up() && capture_width > dimensions.width() {
-            capture_width = dimensions.width();
+        if !settings.scale_up() && capture_width > aspect_dimensions.width() {
+            capture_width = aspect_dimensions.width();
         }
-        let capture_height =
-            (capture_width as f64 / dimensions.width() as f64) * dimensions.height() as f64;
+        let capture_height = (capture_width as f64 / aspect_dimensions.width() as f64)
+            * aspect_dimensions.height() as f64;
         let capture_dimensions = Dimensions::new(capture_width, capture_height as u32);
-        let filter = create_filter_graph(&decoder, &stream, &capture_dimensions)?;
+        let clock = Clock::new(stream.time_base(), stream.start_time());
+        let filter = create_filter_graph(
+            &decoder,
+            &clock,
+            &capture_dimensions,
+            settings.tonemap(),
+            settings.scale_quality(),
+            subtitles_filter.as_deref(),
+        )?;
         let pixel_format = decoder.format();
+        let frame_rate = match stream.avg_frame_rate() {
+            rate if rate.numerator() == 0 => stream.rate(),
+            rate => rate,
+        };
         Ok(Self {
             path: path.as_ref().to_path_buf(),
-            duration: input.duration(),
+            duration,
             pixel_format,
             dimensions,
             capture_dimensions,
+            frame_rate,
+            seek_mode: settings.seek_mode(),
+            clock,
+            last_frame_ts: None,
+            sequential_state: None,
+            subtitles_filter,
+            bitmap_subtitles,
             video_stream_idx: stream.index(),
-            interval: stream.frames() / settings.num_captures() as i64,
             input,
+            decoder,
             filter,
         })
     }
 
+    /// The range of the video to capture frames from, with the amounts to skip at the start and the
+    /// end of the video removed. If the trims would leave nothing to capture, then they're ignored
+    /// and the whole video is used.
+    pub fn capture_range(&self, settings: &Settings) -> (i64, i64) {
+        let start_at = settings.skip().of(self.duration);
+        let end_at = self.duration - settings.trim_end().of(self.duration);
+        if start_at >= end_at {
+            log::warn!(
+                "Skipping {} and trimming {} from the end of {} leaves nothing to capture; using \
+                 the whole video instead.",
+                settings.skip(),
+                settings.trim_end(),
+                self.path.display()
+            );
+            (0, self.duration)
+        } else {
+            (start_at, end_at)
+        }
+    }
+
     /// Generates a list of timestamps where individual frames should be captured.
-    pub fn generate_capture_times(&self, settings: &Settings) -> Vec<i64> {
-        let start_at = (self.duration as f64 * settings.skip()) as i64;
-        let back_trim = (self.duration as f64 * BACK_TRIM_AMOUNT) as i64;
-        let interval =
-            ((self.duration - start_at - back_trim) as f64 / settings.num_captures() as f64) as i64;
-        repeat(true)
-            .take(settings.num_captures() as usize)
-            .enumerate()
-            .map(|(i, _)| i as i64 * interval + start_at)
+    pub fn generate_capture_times(&mut self, settings: &Settings) -> Result<Vec<Timestamp>> {
+        self.generate_n_capture_times(settings, settings.num_captures() as usize)
+    }
+
+    /// Generates a list of timestamps where individual frames should be captured, with
+    /// `num_captures` of them for the modes that capture a fixed number of frames.
+    pub fn generate_n_capture_times(
+        &mut self,
+        settings: &Settings,
+        num_captures: usize,
+    ) -> Result<Vec<Timestamp>> {
+        let (mut start_at, mut end_at) = self.capture_range(settings);
+        if settings.detect_intro_credits() {
+            (start_at, end_at) = detect::narrow_capture_range(self, start_at, end_at)?;
+            // The samples taken to detect them aren't captures, so the first capture shouldn't be
+            // compared against them.
+            self.last_frame_ts = None;
+        }
+        let times = match settings.capture_mode() {
+            CaptureMode::Segments => {
+                let interval = ((end_at - start_at) as f64 / num_captures as f64) as i64;
+                repeat(true)
+                    .take(num_captures)
+                    .enumerate()
+                    .map(|(i, _)| i as i64 * interval + start_at)
+                    .collect()
+            }
+            CaptureMode::Interval => {
+                let interval = (settings.interval() * AV_TIME_BASE as f64) as usize;
+                (start_at..end_at).step_by(interval.max(1)).collect()
+            }
+            CaptureMode::Chapters => self.chapter_capture_times(num_captures, start_at, end_at),
+            CaptureMode::Keyframes => self
+                .keyframe_times(start_at, end_at)?
+                .into_iter()
+                .step_by(settings.keyframe_interval() as usize)
+                .collect(),
+        };
+        Ok(times.into_iter().map(Timestamp::new).collect())
+    }
+
+    /// Spreads the captures across the chapters that fall within `start_at` and `end_at`. Each
+    /// chapter's captures are centered in equal segments of the chapter, so that they don't land on
+    /// the (often black) chapter boundaries.
+    fn chapter_capture_times(&self, num_captures: usize, start_at: i64, end_at: i64) -> Vec<i64> {
+        let ranges: Vec<(i64, i64)> = self
+            .chapters()
+            .iter()
+            .map(|chapter| (chapter.start().max(start_at), chapter.end().min(end_at)))
+            .filter(|(start, end)| start < end)
+            .collect();
+        let ranges = if ranges.is_empty() {
+            log::warn!(
+                "{} has no chapters; capturing equal segments instead.",
+                self.path.display()
+            );
+            vec![(start_at, end_at)]
+        } else {
+            ranges
+        };
+        let lengths: Vec<i64> = ranges.iter().map(|(start, end)| end - start).collect();
+        let counts = distribute_captures(&lengths, num_captures);
+        ranges
+            .iter()
+            .zip(counts)
+            .flat_map(|((start, end), count)| {
+                let step = (end - start) as f64 / count as f64;
+                (0..count).map(move |i| start + ((i as f64 + 0.5) * step) as i64)
+            })
+            .collect()
+    }
+
+    /// Converts a list of user-provided capture times into timestamps. Times at or past the end of
+    /// the video have no frame to capture, so they're dropped.
+    pub fn resolve_capture_times(&self, times: &[CaptureTime]) -> Result<Vec<Timestamp>> {
+        let mut timestamps = Vec::with_capacity(times.len());
+        for time in times {
+            let timestamp = match *time {
+                CaptureTime::Seconds(seconds) => Timestamp::from_seconds(seconds),
+                CaptureTime::Frame(_) if self.frame_rate.numerator() == 0 => {
+                    return Err(Error::Other {
+                        message: format!(
+                            "Cannot capture by frame number: {} has an unknown frame rate.",
+                            self.path.display()
+                        ),
+                    })
+                }
+                CaptureTime::Frame(frame) => Timestamp::new(
+                    (frame as i64).rescale(self.frame_rate.invert(), rescale::TIME_BASE),
+                ),
+            };
+            if timestamp.av_time() >= self.duration {
+                log::warn!(
+                    "Dropping capture time {:?} ({}), which is past the end of {} ({})",
+                    time,
+                    timestamp,
+                    self.path.display(),
+                    Timestamp::new(self.duration)
+                );
+                continue;
+            }
+            timestamps.push(timestamp);
+        }
+        if timestamps.is_empty() {
+            return Err(Error::Other {
+                message: format!(
+                    "None of the capture times are within {}.",
+                    self.path.display()
+                ),
+            });
+        }
+        Ok(timestamps)
+    }
+
+    /// The video stream's clock, counting in the `AV_TIME_BASE` units used to seek in the input and
+    /// by the chapters. The container's start time isn't used, since it's the start of whichever
+    /// stream starts first (often the audio), which isn't necessarily the video.
+    fn av_time_clock(&self) -> Clock {
+        self.clock.with_time_base(rescale::TIME_BASE)
+    }
+
+    /// Finds the timestamps of all of the keyframes between `start_at` and `end_at`. Only packets
+    /// are read, so this is a lot cheaper than decoding the video.
+    fn keyframe_times(&mut self, start_at: i64, end_at: i64) -> Result<Vec<i64>> {
+        let clock = self.clock;
+        let video_stream_idx = self.video_stream_idx;
+        let seek_to = self.av_time_clock().to_clock(Timestamp::new(start_at));
+        self.input.seek(seek_to, ..seek_to)?;
+        let times = self
+            .input
+            .packets()
+            .filter(|(s, p)| s.index() == video_stream_idx && p.is_key())
+            .filter_map(|(_, p)| p.pts().or_else(|| p.dts()))
+            .map(|pts| clock.from_clock(pts).av_time())
+            .skip_while(|ts| *ts < start_at)
+            .take_while(|ts| *ts < end_at)
+            .collect();
+        Ok(times)
+    }
+
+    /// The chapters of the video, in order.
+    pub fn chapters(&self) -> Vec<ChapterInfo> {
+        let av_time_clock = self.av_time_clock();
+        let from_clock = |time: i64, time_base: Rational| {
+            av_time_clock
+                .from_clock(time.rescale(time_base, rescale::TIME_BASE))
+                .av_time()
+        };
+        self.input
+            .chapters()
+            .map(|chapter| ChapterInfo {
+                title: chapter.metadata().get("title").map(String::from),
+                start: from_clock(chapter.start(), chapter.time_base()),
+                end: from_clock(chapter.end(), chapter.time_base()),
+            })
             .collect()
     }
 
@@ -189,32 +865,74 @@ impl VidInfo {
         self.dimensions.height()
     }
 
-    /// Returns the video stream for the underlying video file.
-    pub fn stream(&self) -> Result<Stream<'_>> {
-        find_best_stream(&self.input, &self.path)
+    /// The file's embedded cover art; see [`cover_art`].
+    pub fn cover_art(&self) -> Option<Vec<u8>> {
+        cover_art(&self.input)
     }
 
-    pub fn img_file_name(&self) -> String {
-        img_file_name(&self.path)
+    /// The peak audio level in each of `buckets` equal parts of the range between `start` and
+    /// `end`, or `None` if the file has no audio.
+    pub fn audio_levels(
+        &mut self,
+        start: Timestamp,
+        end: Timestamp,
+        buckets: usize,
+    ) -> Result<Option<Vec<f32>>> {
+        let levels = waveform::audio_levels(&mut self.input, &self.clock, start, end, buckets)?;
+        // Decoding the audio moved the input, so sequential decoding has to start over.
+        self.sequential_state = None;
+        Ok(levels)
     }
 
-    fn create_decoder(&self) -> Result<VideoDecoder> {
-        Ok(CodecContext::from_parameters(self.stream()?.parameters())?
-            .decoder()
-            .video()?)
+    /// The text subtitles to caption the captures with, if the video has any (in the provided
+    /// language).
+    pub fn caption_subtitles(&mut self, language: Option<&str>) -> Result<Option<TextSubtitles>> {
+        let subtitles = TextSubtitles::open(&mut self.input, &self.path, language, &self.clock)?;
+        // Reading the subtitles moved the input, so sequential decoding has to start over.
+        self.sequential_state = None;
+        Ok(subtitles)
     }
 
-    fn get_actual_size(&self, frame: &Video) -> Dimensions {
-        Dimensions((frame.stride(0) / 3) as u32, frame.height())
+    /// The index of the video stream being captured.
+    pub fn video_stream_index(&self) -> usize {
+        self.video_stream_idx
     }
 
-    /// Gets the frame image at (or near) the provided timestamp.
-    pub fn get_frame_at(&mut self, timestamp: i64) -> Result<(Dimensions, Vec<u8>)> {
-        let mut decoder = self.create_decoder()?;
-        self.input.seek(timestamp, timestamp..self.duration)?;
+    /// The indices of all of the file's video streams, leaving out attached pictures.
+    pub fn video_stream_indices(&self) -> Vec<usize> {
+        self.input
+            .streams()
+            .filter(|s| {
+                s.parameters().medium() == MediaType::Video
+                    && !s.disposition().contains(Disposition::ATTACHED_PIC)
+            })
+            .map(|s| s.index())
+            .collect()
+    }
+
+    /// Returns the video stream for the underlying video file.
+    pub fn stream(&self) -> Result<Stream<'_>> {
+        self.input
+            .stream(self.video_stream_idx)
+            .ok_or_else(|| Error::NoVideoStream {
+                path: self.path.clone(),
+            })
+    }
+
+    pub fn img_file_name(&self) -> String {
+        img_file_name(&self.path)
+    }
+
+    /// Decodes the first frame at or after the keyframe following `timestamp`.
+    fn decode_fast(&mut self, timestamp: Timestamp) -> Result<Video> {
+        let av_time_clock = self.av_time_clock();
+        let seek_to = av_time_clock.to_clock(timestamp);
+        let end = av_time_clock.to_clock(Timestamp::new(self.duration));
+        self.input.seek(seek_to, seek_to..end)?;
         let mut frame = Video::empty();
         // Done to prevent a borrow of self
         let video_stream_idx = self.video_stream_idx;
+        let decoder = &mut self.decoder;
         self.input
             .packets()
             .filter_map(|(s, p)| {
@@ -231,6 +949,184 @@ impl VidInfo {
                 decoder.receive_frame(&mut frame).is_err()
             })
             .last();
+        Ok(frame)
+    }
+
+    /// Seeks to the keyframe before `timestamp` and decodes forward until reaching the frame at
+    /// `timestamp`. If the video ends first, then the last frame is used.
+    fn decode_accurate(&mut self, timestamp: Timestamp) -> Result<Video> {
+        let target = self.clock.to_clock(timestamp);
+        let video_stream_idx = self.video_stream_idx;
+        self.input
+            .seek_to_frame(video_stream_idx as i32, target, SeekFlags::BACKWARD)?;
+        self.decode_until(target)
+    }
+
+    /// Decodes forward from the current position of the input until reaching the frame at or after
+    /// `target` (in the stream's time base). If the video ends first, then the last frame decoded
+    /// is used.
+    fn decode_until(&mut self, target: i64) -> Result<Video> {
+        let video_stream_idx = self.video_stream_idx;
+        let decoder = &mut self.decoder;
+        let mut frame = Video::empty();
+        let mut last_frame = Video::empty();
+        // A trailing `None` is used to flush the decoder once the packets run out.
+        let packets = self
+            .input
+            .packets()
+            .filter(|(s, _)| s.index() == video_stream_idx)
+            .map(|(_, p)| Some(p))
+            .chain(once(None));
+        for packet in packets {
+            let sent = match packet {
+                Some(packet) => decoder.send_packet(&packet),
+                None => decoder.send_eof(),
+            };
+            if sent.is_err() {
+                continue;
+            }
+            while decoder.receive_frame(&mut frame).is_ok() {
+                if frame.timestamp().unwrap_or(i64::MIN) >= target {
+                    return Ok(frame);
+                }
+                mem::swap(&mut frame, &mut last_frame);
+            }
+        }
+        Ok(last_frame)
+    }
+
+    /// Reopens the video file so that it can be decoded from the start again.
+    fn rewind(&mut self) -> Result<()> {
+        self.input = ffmpeg::format::input_with_dictionary(&self.path, input_opts())?;
+        self.decoder.flush();
+        self.sequential_state = None;
+        Ok(())
+    }
+
+    /// Decodes the whole video once, from start to finish, without seeking. Each call continues
+    /// where the last one left off, so the timestamps should be requested in order; if one isn't,
+    /// then the video is decoded from the start again.
+    fn decode_sequential(&mut self, timestamp: Timestamp) -> Result<Video> {
+        let target = self.clock.to_clock(timestamp);
+        match &self.sequential_state {
+            // The timestamps are out of order, so start over.
+            Some((last_target, _)) if *last_target > target => self.rewind()?,
+            Some((_, frame))
+                if frame.width() > 0 && frame.timestamp().unwrap_or(i64::MIN) >= target =>
+            {
+                return Ok(frame.clone());
+            }
+            Some(_) => {}
+            // Nothing's been decoded sequentially yet, so make sure we're at the start.
+            None => self.rewind()?,
+        }
+        let mut frame = self.decode_until(target)?;
+        if frame.width() == 0 {
+            // Nothing left to decode, so stick with the last frame.
+            if let Some((_, last_frame)) = &self.sequential_state {
+                frame = last_frame.clone();
+            }
+        }
+        self.sequential_state = Some((target, frame.clone()));
+        Ok(frame)
+    }
+
+    /// Whether a frame is the same one as the last capture.
+    fn is_repeat(&self, frame: &Video) -> bool {
+        self.last_frame_ts.is_some() && frame.timestamp() == self.last_frame_ts
+    }
+
+    /// Seeks to the keyframe before `timestamp` and decodes only that frame.
+    fn decode_keyframe(&mut self, timestamp: Timestamp) -> Result<Video> {
+        let target = self.clock.to_clock(timestamp);
+        let video_stream_idx = self.video_stream_idx;
+        self.input
+            .seek_to_frame(video_stream_idx as i32, target, SeekFlags::BACKWARD)?;
+        let keyframe = self
+            .input
+            .packets()
+            .find(|(s, p)| s.index() == video_stream_idx && p.is_key());
+        let decoder = &mut self.decoder;
+        let mut frame = Video::empty();
+        if let Some((_, packet)) = keyframe {
+            decoder.send_packet(&packet)?;
+            // Decoders with a delay won't give up the frame until they're flushed.
+            if decoder.receive_frame(&mut frame).is_err() {
+                decoder.send_eof()?;
+                decoder.receive_frame(&mut frame)?;
+            }
+        }
+        Ok(frame)
+    }
+
+    /// The way frames are currently being seeked to. This can change from what the settings say if
+    /// seeking doesn't work for the video.
+    pub fn seek_mode(&self) -> SeekMode {
+        self.seek_mode
+    }
+
+    /// Gets the frame image at (or near) the provided timestamp.
+    pub fn get_frame_at(&mut self, timestamp: Timestamp) -> Result<RgbImage> {
+        self.get_frame_with(timestamp, self.seek_mode)
+    }
+
+    /// Gets the frame image at (or near) the provided timestamp, using a specific seek mode.
+    pub fn get_frame_with(
+        &mut self,
+        timestamp: Timestamp,
+        seek_mode: SeekMode,
+    ) -> Result<RgbImage> {
+        if seek_mode != SeekMode::Sequential {
+            // Drop anything left over from the last capture (including the end of stream state if
+            // the decoder was drained).
+            self.decoder.flush();
+        }
+        let frame = match seek_mode {
+            SeekMode::Fast | SeekMode::Accurate => {
+                let mut used_mode = seek_mode;
+                let mut result = if seek_mode == SeekMode::Fast {
+                    self.decode_fast(timestamp)
+                } else {
+                    self.decode_accurate(timestamp)
+                };
+                // Fast seeking only looks forward, so it fails on a perfectly good file when there's
+                // no keyframe after the timestamp. Seeking backwards to the keyframe before it is
+                // tried before giving up on seeking altogether.
+                if seek_mode == SeekMode::Fast && !matches!(&result, Ok(f) if f.width() > 0) {
+                    log::debug!(
+                        "Fast seeking to {} in {} {}; retrying with accurate seeking.",
+                        timestamp,
+                        self.path.display(),
+                        seek_problem(&result)
+                    );
+                    self.decoder.flush();
+                    used_mode = SeekMode::Accurate;
+                    result = self.decode_accurate(timestamp);
+                }
+                match result {
+                    Ok(frame)
+                        if !is_bad_seek(used_mode, frame.width() > 0, self.is_repeat(&frame)) =>
+                    {
+                        frame
+                    }
+                    result => {
+                        log::warn!(
+                            "Seeking in {} {}; decoding it sequentially instead.",
+                            self.path.display(),
+                            seek_problem(&result)
+                        );
+                        self.seek_mode = SeekMode::Sequential;
+                        self.decode_sequential(timestamp)?
+                    }
+                }
+            }
+            SeekMode::Keyframe => self.decode_keyframe(timestamp)?,
+            SeekMode::Sequential => self.decode_sequential(timestamp)?,
+        };
+        self.last_frame_ts = frame.timestamp();
+        let shown_at = frame
+            .timestamp()
+            .map_or(timestamp, |ts| self.clock.from_clock(ts));
         self.filter.get("in").unwrap().source().add(&frame)?;
         let mut rgb_frame = Video::empty();
         self.filter
@@ -238,7 +1134,128 @@ impl VidInfo {
             .unwrap()
             .sink()
             .frame(&mut rgb_frame)?;
-        let data = rgb_frame.data(0).to_vec();
-        Ok((self.get_actual_size(&rgb_frame), data))
+        let mut img = rgb_frame.to_rgb_image().ok_or_else(|| Error::Other {
+            message: format!("Could not convert a frame of {}", self.path.display()),
+        })?;
+        if let Some(subtitles) = &self.bitmap_subtitles {
+            subtitles.draw(&mut img, shown_at);
+        }
+        Ok(img)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Converts a single grey pixel with the provided luma to RGB, the same way captures are.
+    fn convert_grey(luma: u8, space: ColorSpace, range: ColorRange) -> [u8; 3] {
+        let mut graph = Graph::new();
+        let mut buffer_args = vec![
+            "video_size=16x16".to_string(),
+            "pix_fmt=yuv420p".to_string(),
+            "time_base=1/1".to_string(),
+        ];
+        buffer_args.extend(buffer_color_args(space, range));
+        graph
+            .add(
+                &filter::find("buffer").unwrap(),
+                "in",
+                &buffer_args.join(":"),
+            )
+            .unwrap();
+        graph
+            .add(
+                &filter::find("scale").unwrap(),
+                "scale",
+                &scale_color_args(space, range, 16).join(":"),
+            )
+            .unwrap();
+        graph
+            .add(&filter::find("format").unwrap(), "pix_fmt", "rgb24")
+            .unwrap();
+        graph
+            .add(&filter::find("buffersink").unwrap(), "out", "")
+            .unwrap();
+        graph
+            .chain_link(&["in", "scale", "pix_fmt", "out"])
+            .unwrap();
+        graph.validate().unwrap();
+
+        let mut frame = Video::new(PixelFormat::YUV420P, 16, 16);
+        frame.set_color_space(space);
+        frame.set_color_range(range);
+        frame.set_pts(Some(0));
+        frame.data_mut(0).fill(luma);
+        frame.data_mut(1).fill(128);
+        frame.data_mut(2).fill(128);
+        graph.get("in").unwrap().source().add(&frame).unwrap();
+        let mut rgb_frame = Video::empty();
+        graph
+            .get("out")
+            .unwrap()
+            .sink()
+            .frame(&mut rgb_frame)
+            .unwrap();
+        rgb_frame.to_rgb_image().unwrap().get_pixel(8, 8).0
+    }
+
+    fn assert_close(actual: [u8; 3], expected: u8) {
+        assert!(
+            actual.iter().all(|c| c.abs_diff(expected) <= 2),
+            "{:?} is not close to {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_color_range_conversion() {
+        ffmpeg::init().unwrap();
+        // Limited range black and white are stretched out to the full range.
+        assert_close(convert_grey(16, ColorSpace::BT709, ColorRange::MPEG), 0);
+        assert_close(convert_grey(235, ColorSpace::BT709, ColorRange::MPEG), 255);
+        // Full range values are left alone.
+        assert_close(convert_grey(16, ColorSpace::BT709, ColorRange::JPEG), 16);
+        assert_close(
+            convert_grey(255, ColorSpace::BT470BG, ColorRange::JPEG),
+            255,
+        );
+        // Unspecified ranges are treated as limited.
+        assert_close(
+            convert_grey(16, ColorSpace::Unspecified, ColorRange::Unspecified),
+            0,
+        );
+    }
+
+    #[test]
+    fn test_is_bad_seek() {
+        assert!(!is_bad_seek(SeekMode::Fast, true, false));
+        assert!(!is_bad_seek(SeekMode::Fast, true, true));
+        assert!(is_bad_seek(SeekMode::Fast, false, false));
+        assert!(!is_bad_seek(SeekMode::Accurate, true, false));
+        assert!(is_bad_seek(SeekMode::Accurate, true, true));
+        assert!(is_bad_seek(SeekMode::Accurate, false, false));
+    }
+
+    #[test]
+    fn test_color_matrix_name() {
+        assert_eq!(color_matrix_name(ColorSpace::BT709, 480), "bt709");
+        assert_eq!(color_matrix_name(ColorSpace::SMPTE170M, 1080), "bt601");
+        assert_eq!(color_matrix_name(ColorSpace::BT2020NCL, 2160), "bt2020");
+        assert_eq!(color_matrix_name(ColorSpace::Unspecified, 1080), "bt709");
+        assert_eq!(color_matrix_name(ColorSpace::Unspecified, 576), "bt601");
+    }
+
+    #[test]
+    fn test_distribute_captures() {
+        assert_eq!(distribute_captures(&[10, 10, 10], 6), vec![2, 2, 2]);
+        assert_eq!(distribute_captures(&[10, 30], 6), vec![2, 4]);
+        assert_eq!(distribute_captures(&[1, 1000, 1], 2), vec![1, 1, 1]);
+        assert_eq!(
+            distribute_captures(&[10, 10, 10], 7).iter().sum::<usize>(),
+            7
+        );
+        assert_eq!(distribute_captures(&[0, 0], 5), vec![1, 1]);
     }
 }
@@ -0,0 +1,209 @@
+//! Items for drawing a strip showing the audio of a video, to go beneath the captures.
+use ffmpeg::{
+    codec::{context::Context as CodecContext, decoder::Audio as AudioDecoder},
+    format::{
+        context::Input,
+        sample::{Sample, Type as SampleType},
+        stream::Stream,
+    },
+    rescale,
+    util::{frame::audio::Audio, media::Type as MediaType},
+    ChannelLayout,
+};
+use image::{Rgb, RgbImage};
+
+use crate::{
+    time::{Clock, Timestamp},
+    Result,
+};
+
+/// The color of the waveform itself.
+const WAVE_COLOR: Rgb<u8> = Rgb([170, 170, 170]);
+/// The color of the tick marks and the center line.
+const TICK_COLOR: Rgb<u8> = Rgb([255, 255, 255]);
+/// The fraction of the strip's height that the tick marks take up, at both the top and the bottom.
+const TICK_FRACTION: f64 = 0.15;
+
+/// Opens a decoder for an audio stream. Some files don't say how their channels are laid out, so
+/// the default layout for the number of channels is used for them.
+pub fn open_audio_decoder(stream: &Stream) -> Result<AudioDecoder> {
+    let mut decoder = CodecContext::from_parameters(stream.parameters())?
+        .decoder()
+        .audio()?;
+    let layout = match decoder.channel_layout() {
+        layout if layout.channels() > 0 => layout,
+        _ => ChannelLayout::default(decoder.channels() as i32),
+    };
+    decoder.set_channel_layout(layout);
+    Ok(decoder)
+}
+
+/// Adds the samples of a mono frame, starting at `start`, to the peak levels of the buckets they
+/// fall in. Buckets are `bucket_length` `AV_TIME_BASE` units long, starting at `range_start`.
+fn add_samples(
+    levels: &mut [f32],
+    samples: &[f32],
+    rate: u32,
+    start: Timestamp,
+    range_start: Timestamp,
+    bucket_length: f64,
+) {
+    let offset = (start.av_time() - range_start.av_time()) as f64;
+    let sample_length = ffmpeg::ffi::AV_TIME_BASE as f64 / rate as f64;
+    for (idx, sample) in samples.iter().enumerate() {
+        let bucket = (offset + idx as f64 * sample_length) / bucket_length;
+        if bucket < 0.0 {
+            continue;
+        }
+        match levels.get_mut(bucket as usize) {
+            Some(level) => *level = level.max(sample.abs().min(1.0)),
+            None => break,
+        }
+    }
+}
+
+/// Finds the peak level (from 0 to 1) of the best audio stream in each of `num_buckets` equal parts
+/// of the range between `start` and `end`. Returns `None` if the file has no audio. `clock` is the
+/// clock that `start` and `end` are relative to (the video stream's, for a video), so that the
+/// levels line up with the captures even when the audio starts at a different time.
+///
+/// The input is left wherever decoding stopped, so it needs to be seeked before it's used again.
+pub fn audio_levels(
+    input: &mut Input,
+    clock: &Clock,
+    start: Timestamp,
+    end: Timestamp,
+    num_buckets: usize,
+) -> Result<Option<Vec<f32>>> {
+    let Some(stream) = input.streams().best(MediaType::Audio) else {
+        return Ok(None);
+    };
+    let stream_idx = stream.index();
+    let stream_clock = clock.with_time_base(stream.time_base());
+    let mut decoder = open_audio_decoder(&stream)?;
+    let mut resampler = decoder.resampler(
+        Sample::F32(SampleType::Packed),
+        ChannelLayout::MONO,
+        decoder.rate(),
+    )?;
+    let mut levels = vec![0.0; num_buckets];
+    let bucket_length = (end.av_time() - start.av_time()) as f64 / num_buckets.max(1) as f64;
+    if bucket_length <= 0.0 {
+        return Ok(Some(levels));
+    }
+    let seek_to = clock.with_time_base(rescale::TIME_BASE).to_clock(start);
+    input.seek(seek_to, ..seek_to)?;
+    // Decoded frames can have an unknown channel layout, which the resampler won't accept, so they
+    // get the decoder's one (which `open_audio_decoder` makes sure is known) instead.
+    let layout = decoder.channel_layout();
+    let mut frame = Audio::empty();
+    for (s, packet) in input.packets() {
+        if s.index() != stream_idx {
+            continue;
+        }
+        if decoder.send_packet(&packet).is_err() {
+            continue;
+        }
+        while decoder.receive_frame(&mut frame).is_ok() {
+            let Some(pts) = frame.timestamp() else {
+                continue;
+            };
+            let frame_start = stream_clock.from_clock(pts);
+            if frame_start > end {
+                return Ok(Some(levels));
+            }
+            frame.set_channel_layout(layout);
+            let mut mono = Audio::empty();
+            resampler.run(&frame, &mut mono)?;
+            add_samples(
+                &mut levels,
+                mono.plane::<f32>(0),
+                decoder.rate(),
+                frame_start,
+                start,
+                bucket_length,
+            );
+        }
+    }
+    Ok(Some(levels))
+}
+
+/// Draws a waveform of the provided levels (one per column), with tick marks at the provided
+/// columns.
+pub fn render_waveform(levels: &[f32], height: u32, ticks: &[u32]) -> RgbImage {
+    let width = levels.len() as u32;
+    let mut img = RgbImage::new(width, height);
+    if height == 0 {
+        return img;
+    }
+    let center = height / 2;
+    for (x, level) in levels.iter().enumerate() {
+        let half = (level.clamp(0.0, 1.0) * center as f32).round() as u32;
+        for y in center.saturating_sub(half)..(center + half).min(height) {
+            img.put_pixel(x as u32, y, WAVE_COLOR);
+        }
+        img.put_pixel(x as u32, center.min(height - 1), TICK_COLOR);
+    }
+    let tick_height = ((height as f64 * TICK_FRACTION) as u32).clamp(1, height);
+    for x in ticks.iter().filter(|x| **x < width) {
+        for y in (0..tick_height).chain(height - tick_height..height) {
+            img.put_pixel(*x, y, TICK_COLOR);
+        }
+    }
+    img
+}
+
+/// The column of the strip that a timestamp falls on, for a strip `width` pixels wide that spans
+/// `start` to `end`.
+pub fn tick_position(timestamp: Timestamp, start: Timestamp, end: Timestamp, width: u32) -> u32 {
+    let length = (end.av_time() - start.av_time()).max(1) as f64;
+    let fraction = (timestamp.av_time() - start.av_time()) as f64 / length;
+    ((fraction.clamp(0.0, 1.0) * width as f64) as u32).min(width.saturating_sub(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_samples() {
+        let mut levels = vec![0.0; 4];
+        // 4 samples a second, in buckets of half a second.
+        let samples = [0.1, -0.5, 0.2, 0.3, -0.9, 0.0, 2.0, 0.4];
+        add_samples(
+            &mut levels,
+            &samples,
+            4,
+            Timestamp::from_seconds(0.5),
+            Timestamp::ZERO,
+            500_000.0,
+        );
+        assert_eq!(levels, vec![0.0, 0.5, 0.3, 0.9]);
+    }
+
+    #[test]
+    fn test_render_waveform() {
+        let img = render_waveform(&[0.0, 1.0, 0.5], 20, &[2]);
+        assert_eq!(img.dimensions(), (3, 20));
+        assert_eq!(*img.get_pixel(0, 2), Rgb([0, 0, 0]));
+        assert_eq!(*img.get_pixel(1, 0), WAVE_COLOR);
+        assert_eq!(*img.get_pixel(1, 19), WAVE_COLOR);
+        assert_eq!(*img.get_pixel(2, 4), Rgb([0, 0, 0]));
+        assert_eq!(*img.get_pixel(2, 6), WAVE_COLOR);
+        assert_eq!(*img.get_pixel(2, 0), TICK_COLOR);
+        assert_eq!(*img.get_pixel(2, 19), TICK_COLOR);
+    }
+
+    #[test]
+    fn test_tick_position() {
+        let start = Timestamp::from_seconds(10.0);
+        let end = Timestamp::from_seconds(20.0);
+        assert_eq!(tick_position(start, start, end, 100), 0);
+        assert_eq!(
+            tick_position(Timestamp::from_seconds(15.0), start, end, 100),
+            50
+        );
+        assert_eq!(tick_position(end, start, end, 100), 99);
+        assert_eq!(tick_position(Timestamp::ZERO, start, end, 100), 0);
+    }
+}